@@ -0,0 +1,90 @@
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_halt as _;
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::digital::Wait;
+
+use rp_hal_async::{AsyncFlexPin, IntoAsync, WithTimeout};
+
+use rp_pico::hal;
+
+use hal::fugit::ExtU64;
+
+use static_cell::StaticCell;
+
+use embassy_executor::Executor;
+
+/// Start signal of a DHT22 sensor
+const START_US: u32 = 1_000u32;
+/// The sensor answers pulling the line low within 40 µs
+const RESPONSE_US: u64 = 200u64;
+const PERIOD_MS: u32 = 2_000u32;
+
+#[embassy_executor::task]
+async fn simple() -> ! {
+    let mut pac = hal::pac::Peripherals::take().unwrap();
+
+    let mut watchdog = hal::Watchdog::new(pac.WATCHDOG);
+
+    let clocks = hal::clocks::init_clocks_and_plls(
+        rp_pico::XOSC_CRYSTAL_FREQ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .unwrap();
+
+    unsafe {
+        rp_hal_async::init();
+    }
+
+    let mut timer = hal::Timer::new(pac.TIMER, &mut pac.RESETS, &clocks);
+    let mut alarm = timer.alarm_1().unwrap().into_async();
+
+    defmt::info!("rp-hal-async-flex-pin");
+
+    let sio = hal::Sio::new(pac.SIO);
+
+    let pins = hal::gpio::Pins::new(
+        pac.IO_BANK0,
+        pac.PADS_BANK0,
+        sio.gpio_bank0,
+        &mut pac.RESETS,
+    );
+
+    // open drain line with an external pull-up
+    let mut line = AsyncFlexPin::new(pins.gpio0.into_floating_input());
+
+    loop {
+        line.set_low().unwrap();
+        line.set_as_output();
+        alarm.delay_us(START_US).await;
+
+        line.set_as_input();
+        match line
+            .wait_for_falling_edge()
+            .with_alarm_timeout(&mut alarm, RESPONSE_US.micros())
+            .await
+        {
+            Ok(_) => defmt::info!("sensor answered"),
+            Err(timeout) => defmt::info!("no sensor: {}", timeout),
+        }
+
+        alarm.delay_ms(PERIOD_MS).await;
+    }
+}
+
+#[hal::entry]
+fn main() -> ! {
+    static EXECUTOR: StaticCell<Executor> = StaticCell::new();
+    let executor = EXECUTOR.init(Executor::new());
+
+    executor.run(|spawner| spawner.spawn(simple()).unwrap());
+}
@@ -0,0 +1,88 @@
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_halt as _;
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::digital::Wait;
+
+use rp_hal_async::{IntoAsync, WithTimeout};
+
+use rp_pico::hal;
+
+use hal::fugit::ExtU64;
+
+use static_cell::StaticCell;
+
+use embassy_executor::Executor;
+
+const PULSE_US: u32 = 500u32;
+/// Longest time the other devices may hold the line low
+const RELEASE_MS: u64 = 100u64;
+const PERIOD_MS: u32 = 1_000u32;
+
+#[embassy_executor::task]
+async fn simple() -> ! {
+    let mut pac = hal::pac::Peripherals::take().unwrap();
+
+    let mut watchdog = hal::Watchdog::new(pac.WATCHDOG);
+
+    let clocks = hal::clocks::init_clocks_and_plls(
+        rp_pico::XOSC_CRYSTAL_FREQ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .unwrap();
+
+    unsafe {
+        rp_hal_async::init();
+    }
+
+    let mut timer = hal::Timer::new(pac.TIMER, &mut pac.RESETS, &clocks);
+    let mut alarm = timer.alarm_1().unwrap().into_async();
+
+    defmt::info!("rp-hal-async-output-readback");
+
+    let sio = hal::Sio::new(pac.SIO);
+
+    let pins = hal::gpio::Pins::new(
+        pac.IO_BANK0,
+        pac.PADS_BANK0,
+        sio.gpio_bank0,
+        &mut pac.RESETS,
+    );
+
+    // wired-AND line: the other devices hold it low until they are ready
+    let mut line = pins.gpio0.into_push_pull_output().into_async();
+
+    loop {
+        line.set_low().unwrap();
+        alarm.delay_us(PULSE_US).await;
+
+        line.set_high().unwrap();
+        match line
+            .wait_for_high()
+            .with_alarm_timeout(&mut alarm, RELEASE_MS.millis())
+            .await
+        {
+            Ok(_) => defmt::info!("line released"),
+            Err(timeout) => defmt::info!("line still held: {}", timeout),
+        }
+
+        alarm.delay_ms(PERIOD_MS).await;
+    }
+}
+
+#[hal::entry]
+fn main() -> ! {
+    static EXECUTOR: StaticCell<Executor> = StaticCell::new();
+    let executor = EXECUTOR.init(Executor::new());
+
+    executor.run(|spawner| spawner.spawn(simple()).unwrap());
+}
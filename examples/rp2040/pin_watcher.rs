@@ -0,0 +1,76 @@
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_halt as _;
+
+use embedded_hal_async::digital::Wait;
+
+use rp_hal_async::{AsyncInputPin, IntoAsync, PinWatcher};
+
+use rp_pico::hal;
+
+use hal::gpio::{bank0::Gpio0, PullUp};
+
+use static_cell::StaticCell;
+
+use embassy_executor::{Executor, Spawner};
+
+type Watcher = PinWatcher<'static, Gpio0, PullUp>;
+
+#[embassy_executor::task]
+async fn levels(mut watcher: Watcher) -> ! {
+    loop {
+        watcher.wait_for_low().await.unwrap();
+        defmt::info!("low");
+
+        watcher.wait_for_high().await.unwrap();
+        defmt::info!("high");
+    }
+}
+
+#[embassy_executor::task]
+async fn edges(mut watcher: Watcher) -> ! {
+    let mut count = 0u32;
+    loop {
+        watcher.wait_for_any_edge().await.unwrap();
+
+        count = count.wrapping_add(1);
+        defmt::info!("edge {}", count);
+    }
+}
+
+#[embassy_executor::task]
+async fn simple(spawner: Spawner) {
+    static INPUT_PIN: StaticCell<AsyncInputPin<Gpio0, PullUp>> = StaticCell::new();
+
+    let mut pac = hal::pac::Peripherals::take().unwrap();
+
+    unsafe {
+        rp_hal_async::init_without_time_driver();
+    }
+
+    defmt::info!("rp-hal-async-pin-watcher");
+
+    let sio = hal::Sio::new(pac.SIO);
+
+    let pins = hal::gpio::Pins::new(
+        pac.IO_BANK0,
+        pac.PADS_BANK0,
+        sio.gpio_bank0,
+        &mut pac.RESETS,
+    );
+
+    let input_pin = INPUT_PIN.init(pins.gpio0.into_pull_up_input().into_async());
+
+    spawner.spawn(levels(input_pin.watcher())).unwrap();
+    spawner.spawn(edges(input_pin.watcher())).unwrap();
+}
+
+#[hal::entry]
+fn main() -> ! {
+    static EXECUTOR: StaticCell<Executor> = StaticCell::new();
+    let executor = EXECUTOR.init(Executor::new());
+
+    executor.run(|spawner| spawner.spawn(simple(spawner)).unwrap());
+}
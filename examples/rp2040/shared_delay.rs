@@ -0,0 +1,68 @@
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_halt as _;
+
+use embedded_hal_async::delay::DelayNs;
+
+use rp_hal_async::{IntoAsync, SharedAlarm, SharedDelay};
+
+use rp_pico::hal;
+
+use static_cell::StaticCell;
+
+use embassy_executor::{Executor, Spawner};
+
+const DELAYS_MS: [u32; 4] = [250u32, 300u32, 700u32, 1_100u32];
+
+type Alarm = hal::timer::Alarm2;
+
+#[embassy_executor::task(pool_size = 4)]
+async fn blink(id: usize, mut delay: SharedDelay<'static, Alarm>) -> ! {
+    loop {
+        delay.delay_ms(DELAYS_MS[id]).await;
+        defmt::info!("task {} after {} ms", id, DELAYS_MS[id]);
+    }
+}
+
+#[embassy_executor::task]
+async fn simple(spawner: Spawner) {
+    static SHARED: StaticCell<SharedAlarm<Alarm>> = StaticCell::new();
+
+    let mut pac = hal::pac::Peripherals::take().unwrap();
+
+    let mut watchdog = hal::Watchdog::new(pac.WATCHDOG);
+
+    let clocks = hal::clocks::init_clocks_and_plls(
+        rp_pico::XOSC_CRYSTAL_FREQ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .unwrap();
+
+    unsafe {
+        rp_hal_async::init();
+    }
+
+    let mut timer = hal::Timer::new(pac.TIMER, &mut pac.RESETS, &clocks);
+    let shared = SHARED.init(timer.alarm_2().unwrap().into_async().into_shared());
+
+    defmt::info!("rp-hal-async-shared-delay");
+
+    for id in 0..DELAYS_MS.len() {
+        spawner.spawn(blink(id, shared.delay())).unwrap();
+    }
+}
+
+#[hal::entry]
+fn main() -> ! {
+    static EXECUTOR: StaticCell<Executor> = StaticCell::new();
+    let executor = EXECUTOR.init(Executor::new());
+
+    executor.run(|spawner| spawner.spawn(simple(spawner)).unwrap());
+}
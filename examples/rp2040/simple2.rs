@@ -35,7 +35,7 @@ async fn simple() -> ! {
     let timer = hal::Timer::new(pac.TIMER, &mut pac.RESETS, &clocks);
 
     unsafe {
-        rp_hal_async::init(timer);
+        rp_hal_async::init(timer, rp_hal_async::TickConfig::Default);
     }
 
     defmt::info!("rp-hal-async-simple2");
@@ -57,6 +57,9 @@ async fn simple() -> ! {
         Timer::after(Duration::from_millis(DELAY_MS)).await;
         defmt::trace!("done wait");
         led_pin.toggle().unwrap();
+
+        #[cfg(feature = "time_driver-stats")]
+        defmt::debug!("alarms: {}", rp_hal_async::stats::alarm_stats());
     }
 }
 
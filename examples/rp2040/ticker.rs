@@ -0,0 +1,83 @@
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_halt as _;
+
+use embedded_hal::digital::StatefulOutputPin;
+
+use rp_hal_async::IntoAsync;
+
+use rp_pico::hal;
+
+use hal::fugit::ExtU32;
+
+use static_cell::StaticCell;
+
+use embassy_executor::Executor;
+
+const PERIOD_US: u32 = 1_000u32;
+const TICKS_PER_TOGGLE: u32 = 500u32;
+
+#[embassy_executor::task]
+async fn simple() -> ! {
+    let mut pac = hal::pac::Peripherals::take().unwrap();
+
+    let mut watchdog = hal::Watchdog::new(pac.WATCHDOG);
+
+    let clocks = hal::clocks::init_clocks_and_plls(
+        rp_pico::XOSC_CRYSTAL_FREQ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .unwrap();
+
+    unsafe {
+        rp_hal_async::init();
+    }
+
+    let mut timer = hal::Timer::new(pac.TIMER, &mut pac.RESETS, &clocks);
+    let mut ticker = timer
+        .alarm_1()
+        .unwrap()
+        .into_async()
+        .into_ticker(PERIOD_US.micros());
+
+    defmt::info!("rp-hal-async-ticker");
+
+    let sio = hal::Sio::new(pac.SIO);
+
+    let pins = hal::gpio::Pins::new(
+        pac.IO_BANK0,
+        pac.PADS_BANK0,
+        sio.gpio_bank0,
+        &mut pac.RESETS,
+    );
+
+    let mut led_pin = pins.gpio25.into_push_pull_output();
+
+    let mut ticks = 0;
+    loop {
+        if let Err(overrun) = ticker.next().await {
+            defmt::warn!("overrun: {}", overrun);
+        }
+
+        ticks += 1;
+        if ticks == TICKS_PER_TOGGLE {
+            ticks = 0;
+            led_pin.toggle().unwrap();
+        }
+    }
+}
+
+#[hal::entry]
+fn main() -> ! {
+    static EXECUTOR: StaticCell<Executor> = StaticCell::new();
+    let executor = EXECUTOR.init(Executor::new());
+
+    executor.run(|spawner| spawner.spawn(simple()).unwrap());
+}
@@ -0,0 +1,81 @@
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_halt as _;
+
+use embedded_hal::digital::StatefulOutputPin;
+use embedded_hal_async::digital::Wait;
+
+use rp_hal_async::{IntoAsync, WithTimeout};
+
+use rp_pico::hal;
+
+use hal::fugit::ExtU64;
+
+use static_cell::StaticCell;
+
+use embassy_executor::Executor;
+
+const TIMEOUT_MS: u64 = 2_000u64;
+
+#[embassy_executor::task]
+async fn simple() -> ! {
+    let mut pac = hal::pac::Peripherals::take().unwrap();
+
+    let mut watchdog = hal::Watchdog::new(pac.WATCHDOG);
+
+    let clocks = hal::clocks::init_clocks_and_plls(
+        rp_pico::XOSC_CRYSTAL_FREQ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .unwrap();
+
+    unsafe {
+        rp_hal_async::init();
+    }
+
+    let mut timer = hal::Timer::new(pac.TIMER, &mut pac.RESETS, &clocks);
+    let mut alarm = timer.alarm_1().unwrap().into_async();
+
+    defmt::info!("rp-hal-async-timeout");
+
+    let sio = hal::Sio::new(pac.SIO);
+
+    let pins = hal::gpio::Pins::new(
+        pac.IO_BANK0,
+        pac.PADS_BANK0,
+        sio.gpio_bank0,
+        &mut pac.RESETS,
+    );
+
+    let mut led_pin = pins.gpio25.into_push_pull_output();
+    let mut input_pin = pins.gpio0.into_pull_up_input().into_async();
+
+    loop {
+        match input_pin
+            .wait_for_falling_edge()
+            .with_alarm_timeout(&mut alarm, TIMEOUT_MS.millis())
+            .await
+        {
+            Ok(_) => defmt::info!("falling edge"),
+            Err(timeout) => {
+                defmt::info!("{}", timeout);
+                led_pin.toggle().unwrap();
+            }
+        }
+    }
+}
+
+#[hal::entry]
+fn main() -> ! {
+    static EXECUTOR: StaticCell<Executor> = StaticCell::new();
+    let executor = EXECUTOR.init(Executor::new());
+
+    executor.run(|spawner| spawner.spawn(simple()).unwrap());
+}
@@ -0,0 +1,94 @@
+#![no_std]
+#![no_main]
+
+use core::future::{pending, poll_fn};
+use core::task::Poll;
+
+use defmt_rtt as _;
+use panic_halt as _;
+
+use rp_hal_async::{IntoAsync, WithTimeout};
+
+use rp_pico::hal;
+
+use hal::fugit::ExtU64;
+
+use static_cell::StaticCell;
+
+use embassy_executor::Executor;
+
+const FIRST_MS: u64 = 5u64;
+const SECOND_MS: u64 = 10u64;
+const ROUNDS_PER_LOG: u32 = 100;
+
+/// Ready on the second poll, once the alarm of the timeout is armed
+async fn yield_once() {
+    let mut yielded = false;
+
+    poll_fn(|cx| {
+        if yielded {
+            Poll::Ready(())
+        } else {
+            yielded = true;
+            cx.waker().wake_by_ref();
+
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+#[embassy_executor::task]
+async fn simple() -> ! {
+    let mut pac = hal::pac::Peripherals::take().unwrap();
+
+    let mut watchdog = hal::Watchdog::new(pac.WATCHDOG);
+
+    let clocks = hal::clocks::init_clocks_and_plls(
+        rp_pico::XOSC_CRYSTAL_FREQ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .unwrap();
+
+    unsafe {
+        rp_hal_async::init_without_time_driver();
+    }
+
+    let mut timer = hal::Timer::new(pac.TIMER, &mut pac.RESETS, &clocks);
+    let mut alarm = timer.alarm_2().unwrap().into_async();
+
+    defmt::info!("rp-hal-async-timeout-reuse");
+
+    let mut rounds = 0u32;
+    loop {
+        // wins the race: the alarm is dropped before its deadline
+        yield_once()
+            .with_alarm_timeout(&mut alarm, FIRST_MS.millis())
+            .await
+            .unwrap();
+
+        // times out on the same alarm, past the deadline of the first one
+        pending::<()>()
+            .with_alarm_timeout(&mut alarm, SECOND_MS.millis())
+            .await
+            .unwrap_err();
+
+        rounds += 1;
+        if rounds % ROUNDS_PER_LOG == 0 {
+            defmt::info!("{} rounds of two timeouts", rounds);
+        }
+    }
+}
+
+#[hal::entry]
+fn main() -> ! {
+    static EXECUTOR: StaticCell<Executor> = StaticCell::new();
+    let executor = EXECUTOR.init(Executor::new());
+
+    executor.run(|spawner| spawner.spawn(simple()).unwrap());
+}
@@ -0,0 +1,94 @@
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_halt as _;
+
+use embedded_hal_async::digital::Wait;
+
+use rp_hal_async::{IntoAsync, WithTimeout};
+
+use rp_pico::hal;
+
+use hal::fugit::ExtU64;
+
+use static_cell::StaticCell;
+
+use embassy_executor::Executor;
+
+const WATCH_MS: u64 = 10u64;
+
+macro_rules! watch_all {
+    ($pins:ident, $alarm:ident: $($n:literal => $gpio:ident),+) => {
+        $(
+            let mut $gpio = $pins.$gpio.into_pull_up_input().into_async();
+        )+
+
+        loop {
+            $(
+                if let Ok(result) = $gpio
+                    .wait_for_any_edge()
+                    .with_alarm_timeout(&mut $alarm, WATCH_MS.millis())
+                    .await
+                {
+                    result.unwrap();
+                    defmt::info!("edge on gpio{}", $n);
+                }
+            )+
+        }
+    };
+}
+
+#[embassy_executor::task]
+async fn simple() -> ! {
+    let mut pac = hal::pac::Peripherals::take().unwrap();
+
+    let mut watchdog = hal::Watchdog::new(pac.WATCHDOG);
+
+    let clocks = hal::clocks::init_clocks_and_plls(
+        rp_pico::XOSC_CRYSTAL_FREQ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .unwrap();
+
+    unsafe {
+        rp_hal_async::init();
+    }
+
+    let mut timer = hal::Timer::new(pac.TIMER, &mut pac.RESETS, &clocks);
+    let mut alarm = timer.alarm_1().unwrap().into_async();
+
+    defmt::info!("rp-hal-async-wait-all-pins");
+
+    let sio = hal::Sio::new(pac.SIO);
+
+    let pins = hal::gpio::Pins::new(
+        pac.IO_BANK0,
+        pac.PADS_BANK0,
+        sio.gpio_bank0,
+        &mut pac.RESETS,
+    );
+
+    watch_all! {
+        pins, alarm:
+        0 => gpio0, 1 => gpio1, 2 => gpio2, 3 => gpio3, 4 => gpio4,
+        5 => gpio5, 6 => gpio6, 7 => gpio7, 8 => gpio8, 9 => gpio9,
+        10 => gpio10, 11 => gpio11, 12 => gpio12, 13 => gpio13, 14 => gpio14,
+        15 => gpio15, 16 => gpio16, 17 => gpio17, 18 => gpio18, 19 => gpio19,
+        20 => gpio20, 21 => gpio21, 22 => gpio22, 23 => gpio23, 24 => gpio24,
+        25 => gpio25, 26 => gpio26, 27 => gpio27, 28 => gpio28, 29 => gpio29
+    }
+}
+
+#[hal::entry]
+fn main() -> ! {
+    static EXECUTOR: StaticCell<Executor> = StaticCell::new();
+    let executor = EXECUTOR.init(Executor::new());
+
+    executor.run(|spawner| spawner.spawn(simple()).unwrap());
+}
@@ -0,0 +1,64 @@
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_halt as _;
+
+use embedded_hal_async::digital::Wait;
+
+use rp_hal_async::{AsyncInputPin, IntoAsync};
+
+use rp_pico::hal;
+
+use hal::gpio::{DynFunction, DynPinId, DynPullType, Pin};
+
+use static_cell::StaticCell;
+
+use embassy_executor::Executor;
+
+/// Buttons as a board configuration would hand them out
+type ErasedPin = Pin<DynPinId, DynFunction, DynPullType>;
+
+#[embassy_executor::task]
+async fn simple() -> ! {
+    let mut pac = hal::pac::Peripherals::take().unwrap();
+
+    unsafe {
+        rp_hal_async::init_without_time_driver();
+    }
+
+    defmt::info!("rp-hal-async-wait-dyn-pins");
+
+    let sio = hal::Sio::new(pac.SIO);
+
+    let pins = hal::gpio::Pins::new(
+        pac.IO_BANK0,
+        pac.PADS_BANK0,
+        sio.gpio_bank0,
+        &mut pac.RESETS,
+    );
+
+    let erased: [ErasedPin; 3] = [
+        pins.gpio0.into_pull_up_input().reconfigure().into_dyn_pin(),
+        pins.gpio1.into_pull_up_input().reconfigure().into_dyn_pin(),
+        pins.gpio2.into_pull_up_input().reconfigure().into_dyn_pin(),
+    ];
+
+    let mut buttons: [AsyncInputPin<DynPinId, DynPullType>; 3] =
+        erased.map(|pin| pin.into_async().ok().unwrap());
+
+    loop {
+        for button in buttons.iter_mut() {
+            defmt::info!("press button {}", button.id().num);
+            button.wait_for_falling_edge().await.unwrap();
+        }
+    }
+}
+
+#[hal::entry]
+fn main() -> ! {
+    static EXECUTOR: StaticCell<Executor> = StaticCell::new();
+    let executor = EXECUTOR.init(Executor::new());
+
+    executor.run(|spawner| spawner.spawn(simple()).unwrap());
+}
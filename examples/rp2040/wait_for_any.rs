@@ -0,0 +1,67 @@
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_halt as _;
+
+use rp_hal_async::{AsyncInputPin, IntoAsync};
+
+use rp_pico::hal;
+
+use hal::gpio::{DynPinId, DynPullType, Interrupt};
+
+use static_cell::StaticCell;
+
+use embassy_executor::Executor;
+
+#[embassy_executor::task]
+async fn simple() -> ! {
+    let mut pac = hal::pac::Peripherals::take().unwrap();
+
+    unsafe {
+        rp_hal_async::init_without_time_driver();
+    }
+
+    defmt::info!("rp-hal-async-wait-for-any");
+
+    let sio = hal::Sio::new(pac.SIO);
+
+    let pins = hal::gpio::Pins::new(
+        pac.IO_BANK0,
+        pac.PADS_BANK0,
+        sio.gpio_bank0,
+        &mut pac.RESETS,
+    );
+
+    // pins of different types, erased to fit in one array
+    let buttons: [AsyncInputPin<DynPinId, DynPullType>; 3] = [
+        pins.gpio0.into_pull_up_input().into_async().into_dyn(),
+        pins.gpio1.into_pull_up_input().into_async().into_dyn(),
+        pins.gpio2.into_pull_down_input().into_async().into_dyn(),
+    ];
+
+    loop {
+        defmt::info!("press or release any button, the one on gpio2 switching to 3V3");
+        let event =
+            rp_hal_async::wait_for_any(&buttons, &[Interrupt::EdgeLow, Interrupt::EdgeHigh]).await;
+
+        let edge = match event.interrupt {
+            Interrupt::EdgeLow => "falling",
+            _ => "rising",
+        };
+        defmt::info!(
+            "{} edge on button {} (gpio{})",
+            edge,
+            event.index,
+            event.id.num
+        );
+    }
+}
+
+#[hal::entry]
+fn main() -> ! {
+    static EXECUTOR: StaticCell<Executor> = StaticCell::new();
+    let executor = EXECUTOR.init(Executor::new());
+
+    executor.run(|spawner| spawner.spawn(simple()).unwrap());
+}
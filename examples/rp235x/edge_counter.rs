@@ -0,0 +1,80 @@
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_halt as _;
+
+use embedded_hal_async::delay::DelayNs;
+
+use rp_hal_async::{Edge, IntoAsync};
+
+use rp235x_hal as hal;
+
+use static_cell::StaticCell;
+
+use embassy_executor::Executor;
+
+#[unsafe(link_section = ".start_block")]
+#[used]
+pub static IMAGE_DEF: hal::block::ImageDef = hal::block::ImageDef::secure_exe();
+
+const XTAL_FREQ_HZ: u32 = 12_000_000u32;
+/// Slower than the pulses, so that edges pile up in the counter
+const WORK_MS: u32 = 100u32;
+
+#[embassy_executor::task]
+async fn simple() -> ! {
+    let mut pac = hal::pac::Peripherals::take().unwrap();
+
+    let mut watchdog = hal::Watchdog::new(pac.WATCHDOG);
+
+    let clocks = hal::clocks::init_clocks_and_plls(
+        XTAL_FREQ_HZ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .unwrap();
+
+    unsafe {
+        rp_hal_async::init_without_time_driver();
+    }
+
+    let mut timer = hal::Timer::new_timer0(pac.TIMER0, &mut pac.RESETS, &clocks);
+    let mut alarm = timer.alarm_1().unwrap().into_async();
+
+    defmt::info!("rp-hal-async-edge-counter");
+
+    let sio = hal::Sio::new(pac.SIO);
+
+    let pins = hal::gpio::Pins::new(
+        pac.IO_BANK0,
+        pac.PADS_BANK0,
+        sio.gpio_bank0,
+        &mut pac.RESETS,
+    );
+
+    let mut input_pin = pins.gpio0.into_pull_up_input().into_async();
+    let mut counter = input_pin.edge_counter(Edge::Falling);
+
+    let mut total = 0u32;
+    loop {
+        let edges = counter.next().await;
+        total = total.wrapping_add(edges);
+
+        defmt::info!("{} edges, {} coalesced, {} total", edges, edges - 1, total);
+
+        alarm.delay_ms(WORK_MS).await;
+    }
+}
+
+#[hal::entry]
+fn main() -> ! {
+    static EXECUTOR: StaticCell<Executor> = StaticCell::new();
+    let executor = EXECUTOR.init(Executor::new());
+
+    executor.run(|spawner| spawner.spawn(simple()).unwrap());
+}
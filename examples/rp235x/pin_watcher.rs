@@ -0,0 +1,92 @@
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_halt as _;
+
+use embedded_hal_async::digital::Wait;
+
+use rp_hal_async::{AsyncInputPin, IntoAsync, PinWatcher};
+
+use rp235x_hal as hal;
+
+use hal::gpio::{bank0::Gpio0, PullUp};
+
+use static_cell::StaticCell;
+
+use embassy_executor::{Executor, Spawner};
+
+#[unsafe(link_section = ".start_block")]
+#[used]
+pub static IMAGE_DEF: hal::block::ImageDef = hal::block::ImageDef::secure_exe();
+
+type Watcher = PinWatcher<'static, Gpio0, PullUp>;
+
+#[embassy_executor::task]
+async fn levels(mut watcher: Watcher) -> ! {
+    loop {
+        watcher.wait_for_low().await.unwrap();
+        defmt::info!("low");
+
+        watcher.wait_for_high().await.unwrap();
+        defmt::info!("high");
+    }
+}
+
+#[embassy_executor::task]
+async fn edges(mut watcher: Watcher) -> ! {
+    let mut count = 0u32;
+    loop {
+        watcher.wait_for_any_edge().await.unwrap();
+
+        count = count.wrapping_add(1);
+        defmt::info!("edge {}", count);
+    }
+}
+
+#[embassy_executor::task]
+async fn simple(spawner: Spawner) {
+    static INPUT_PIN: StaticCell<AsyncInputPin<Gpio0, PullUp>> = StaticCell::new();
+
+    let mut pac = hal::pac::Peripherals::take().unwrap();
+
+    unsafe {
+        rp_hal_async::init_without_time_driver();
+    }
+
+    defmt::info!("rp-hal-async-pin-watcher");
+
+    let sio = hal::Sio::new(pac.SIO);
+
+    let pins = hal::gpio::Pins::new(
+        pac.IO_BANK0,
+        pac.PADS_BANK0,
+        sio.gpio_bank0,
+        &mut pac.RESETS,
+    );
+
+    let input_pin = INPUT_PIN.init(pins.gpio0.into_pull_up_input().into_async());
+
+    spawner.spawn(levels(input_pin.watcher())).unwrap();
+    spawner.spawn(edges(input_pin.watcher())).unwrap();
+}
+
+#[hal::entry]
+fn main() -> ! {
+    static EXECUTOR: StaticCell<Executor> = StaticCell::new();
+    let executor = EXECUTOR.init(Executor::new());
+
+    executor.run(|spawner| spawner.spawn(simple(spawner)).unwrap());
+}
+
+#[unsafe(link_section = ".bi_entries")]
+#[used]
+pub static PICOTOOL_ENTRIES: [hal::binary_info::EntryAddr; 5] = [
+    hal::binary_info::rp_program_name!(c"rp-hal-async-pin-watcher"),
+    hal::binary_info::rp_cargo_version!(),
+    hal::binary_info::rp_program_description!(
+        c"Test for embedded-hal-async / tasks waiting on one pin"
+    ),
+    hal::binary_info::rp_program_url!(c"private"),
+    hal::binary_info::rp_program_build_attribute!(),
+];
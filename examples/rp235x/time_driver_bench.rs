@@ -0,0 +1,200 @@
+//! Benchmark of the time driver locking
+//!
+//! Core 0 measures the cost of `schedule_wake` and the latency of its alarm
+//! interrupt, alone and while core 1 keeps setting alarms. Build it once as
+//! is, the alarms guarded by per-alarm spinlocks, and once with the
+//! `time_driver-critical-section` feature, guarded by the global critical
+//! section as before, to compare the two.
+
+#![no_std]
+#![no_main]
+
+use core::future::poll_fn;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Poll, Waker};
+
+use static_cell::StaticCell;
+
+use defmt_rtt as _;
+use panic_halt as _;
+
+use rp235x_hal as hal;
+
+#[cfg(target_arch = "arm")]
+use hal::multicore::{Multicore, Stack};
+
+use embassy_executor::Executor;
+use embassy_time::{Duration, Instant, Timer};
+use embassy_time_driver::TICK_HZ;
+
+use rp_hal_async::stats;
+
+#[unsafe(link_section = ".start_block")]
+#[used]
+pub static IMAGE_DEF: hal::block::ImageDef = hal::block::ImageDef::secure_exe();
+
+const XTAL_FREQ_HZ: u32 = 12_000_000u32;
+const ITERATIONS: u64 = 10_000;
+const LATENCY_ROUNDS: u32 = 1_000;
+const LATENCY_US: u64 = 50;
+const PAUSE_MS: u64 = 5_000;
+
+const LOCKING: &str = if cfg!(feature = "time_driver-critical-section") {
+    "critical section"
+} else {
+    "alarm spinlocks"
+};
+
+/// Core 1 sets alarms while core 0 measures
+static HAMMER: AtomicBool = AtomicBool::new(false);
+
+#[cfg(target_arch = "arm")]
+static mut CORE1_STACK: Stack<4096> = Stack::new();
+
+async fn task_waker() -> Waker {
+    poll_fn(|cx| Poll::Ready(cx.waker().clone())).await
+}
+
+/// Far enough to never fire, every call moves the deadline earlier so the
+/// alarm is reprogrammed each time
+fn far_deadline() -> u64 {
+    Instant::now().as_ticks() + 3_600 * TICK_HZ
+}
+
+/// Nanoseconds per `schedule_wake` on core 0
+fn measure(waker: &Waker) -> u64 {
+    let mut at = far_deadline();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        embassy_time_driver::schedule_wake(at, waker);
+        at -= 1;
+    }
+
+    (Instant::now() - start).as_micros() * 1_000 / ITERATIONS
+}
+
+/// Worst latency, in ticks, from the deadline to the alarm interrupt of
+/// core 0 over short timers
+async fn latency() -> u32 {
+    stats::reset();
+
+    for _ in 0..LATENCY_ROUNDS {
+        Timer::after(Duration::from_micros(LATENCY_US)).await;
+    }
+
+    stats::alarm_stats()[0].worst_latency
+}
+
+#[cfg(target_arch = "arm")]
+#[embassy_executor::task]
+async fn hammer() -> ! {
+    let waker = task_waker().await;
+    let mut at = far_deadline();
+
+    loop {
+        if HAMMER.load(Ordering::Relaxed) {
+            embassy_time_driver::schedule_wake(at, &waker);
+            at -= 1;
+        }
+    }
+}
+
+#[embassy_executor::task]
+async fn bench() -> ! {
+    let mut pac = hal::pac::Peripherals::take().unwrap();
+
+    let mut watchdog = hal::Watchdog::new(pac.WATCHDOG);
+
+    let clocks = hal::clocks::init_clocks_and_plls(
+        XTAL_FREQ_HZ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .unwrap();
+
+    let timer = hal::Timer::new_timer1(pac.TIMER1, &mut pac.RESETS, &clocks);
+
+    unsafe {
+        rp_hal_async::init(timer, rp_hal_async::TickConfig::Default);
+    }
+
+    defmt::info!("rp-hal-async-time-driver-bench");
+
+    // the HAL starts core 1 on Arm only: on RISC-V both figures are
+    // measured with core 1 stopped
+    #[cfg(target_arch = "arm")]
+    {
+        let mut sio = hal::Sio::new(pac.SIO);
+
+        let mut mc = Multicore::new(&mut pac.PSM, &mut pac.PPB, &mut sio.fifo);
+        let cores = mc.cores();
+        cores[1]
+            .spawn(
+                unsafe { &mut *core::ptr::addr_of_mut!(CORE1_STACK.mem) },
+                || {
+                    static EXECUTOR1: StaticCell<Executor> = StaticCell::new();
+
+                    unsafe {
+                        rp_hal_async::init_core();
+                    }
+
+                    let executor = EXECUTOR1.init(Executor::new());
+                    executor.run(|spawner| spawner.spawn(hammer()).unwrap());
+                },
+            )
+            .unwrap();
+    }
+
+    let waker = task_waker().await;
+
+    loop {
+        HAMMER.store(false, Ordering::Relaxed);
+        let alone = measure(&waker);
+        let latency_alone = latency().await;
+
+        HAMMER.store(true, Ordering::Relaxed);
+        let contended = measure(&waker);
+        let latency_contended = latency().await;
+
+        HAMMER.store(false, Ordering::Relaxed);
+
+        defmt::info!(
+            "{}: {} ns per schedule_wake, {} ns with core 1 setting alarms",
+            LOCKING,
+            alone,
+            contended
+        );
+        defmt::info!(
+            "{}: alarm interrupt latency up to {} ticks, {} ticks with core 1 setting alarms",
+            LOCKING,
+            latency_alone,
+            latency_contended
+        );
+
+        Timer::after(Duration::from_millis(PAUSE_MS)).await;
+    }
+}
+
+#[hal::entry]
+fn main() -> ! {
+    static EXECUTOR: StaticCell<Executor> = StaticCell::new();
+
+    let executor = EXECUTOR.init(Executor::new());
+
+    executor.run(|spawner| spawner.spawn(bench()).unwrap());
+}
+
+#[unsafe(link_section = ".bi_entries")]
+#[used]
+pub static PICOTOOL_ENTRIES: [hal::binary_info::EntryAddr; 5] = [
+    hal::binary_info::rp_program_name!(c"rp-hal-async-time-driver-bench"),
+    hal::binary_info::rp_cargo_version!(),
+    hal::binary_info::rp_program_description!(c"Benchmark of the time driver locking"),
+    hal::binary_info::rp_program_url!(c"private"),
+    hal::binary_info::rp_program_build_attribute!(),
+];
@@ -24,7 +24,7 @@ async fn simple() -> ! {
     let mut pac = hal::pac::Peripherals::take().unwrap();
 
     unsafe {
-        rp_hal_async::init();
+        rp_hal_async::init_without_time_driver();
     }
 
     defmt::info!("rp-hal-async-wait-rising-edge");
@@ -1,10 +1,10 @@
+mod cycles;
+mod shared;
+
 use core::future::Future;
 use core::pin::Pin;
 use core::task::{Context, Poll};
 
-#[cfg(target_arch = "riscv32")]
-compile_error!("TODO: riscv32");
-
 #[cfg(feature = "rp235x")]
 use rp235x_hal as hal;
 
@@ -13,11 +13,40 @@ use rp2040_hal as hal;
 
 use embedded_hal_async::delay::DelayNs;
 
+use fugit::{MicrosDurationU32, MicrosDurationU64};
+
+use hal::timer::Instant;
+
 use crate::{WakerRegister, NUM_CORES};
 
+pub use shared::{SharedAlarm, SharedDelay};
+
+#[cfg(feature = "rp235x")]
+type TimerRegisters = hal::pac::timer0::RegisterBlock;
+
+#[cfg(feature = "rp2040")]
+type TimerRegisters = hal::pac::timer::RegisterBlock;
+
+/// Counter of the timer an alarm belongs to
+pub(crate) trait AlarmCounter {
+    fn now() -> Instant;
+}
+
+fn read_counter(timer: &TimerRegisters) -> Instant {
+    let mut high = timer.timerawh().read().bits();
+    loop {
+        let low = timer.timerawl().read().bits();
+        let high2 = timer.timerawh().read().bits();
+        if high == high2 {
+            return Instant::from_ticks(u64::from(high) << 32 | u64::from(low));
+        }
+        high = high2;
+    }
+}
+
 struct AsyncAlarmFut<'a, A: hal::timer::Alarm> {
     alarm: &'a mut A,
-    delay: u32,
+    deadline: Instant,
     polled: bool,
     done: bool,
 }
@@ -27,40 +56,62 @@ impl<'a, A: hal::timer::Alarm> Unpin for AsyncAlarmFut<'a, A> {}
 impl<'a, A: hal::timer::Alarm> Drop for AsyncAlarmFut<'a, A> {
     fn drop(&mut self) {
         if self.polled && !self.done {
-            self.alarm.disable_interrupt();
+            disarm(self.alarm);
         }
     }
 }
 
+/// Disable the interrupt, disarm the alarm and clear a latched match, so
+/// that an old deadline can not raise the interrupt of the next wait
+fn disarm<A: hal::timer::Alarm>(alarm: &mut A) {
+    alarm.disable_interrupt();
+    alarm.cancel().unwrap();
+    alarm.clear_interrupt();
+}
+
+/// Arm the alarm for `deadline` with its interrupt enabled
+///
+/// The interrupt handler disables the interrupt before waking the task:
+/// called again on every pending poll, also after a wake for someone else.
+fn arm<A: hal::timer::Alarm>(alarm: &mut A, deadline: Instant) {
+    disarm(alarm);
+    alarm.schedule_at(deadline).unwrap();
+    alarm.enable_interrupt();
+}
+
 impl<'a, A> Future for AsyncAlarmFut<'a, A>
 where
-    A: hal::timer::Alarm + WakerRegister,
+    A: hal::timer::Alarm + AlarmCounter + WakerRegister,
 {
     type Output = ();
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
         let this = self.get_mut();
-        if this.polled {
-            if this.done {
-                defmt::error!("poll invoked after ready");
-            } else {
-                this.done = true;
-                this.alarm.disable_interrupt();
-            }
+        if this.done {
+            defmt::error!("poll invoked after ready");
 
-            Poll::Ready(())
-        } else {
-            let duration = fugit::MicrosDurationU32::micros(this.delay);
-
-            this.polled = true;
+            return Poll::Ready(());
+        }
 
-            A::register_waker(&cx.waker());
+        // registered before checking the deadline, not to miss an interrupt
+        // firing in between
+        A::register_waker(cx.waker());
 
-            this.alarm.schedule(duration).unwrap();
-            this.alarm.enable_interrupt();
+        // woken by the alarm or by anything else sharing the waker: only the
+        // counter tells whether the deadline is reached
+        if A::now() >= this.deadline {
+            if this.polled {
+                disarm(this.alarm);
+            }
+            this.done = true;
 
-            Poll::Pending
+            return Poll::Ready(());
         }
+
+        this.polled = true;
+        arm(this.alarm, this.deadline);
+
+        Poll::Pending
     }
 }
 
@@ -69,68 +120,300 @@ where
     A: hal::timer::Alarm,
 {
     alarm: A,
+    busy_wait: Option<BusyWait>,
+}
+
+/// Delays handled spinning on the core cycle counter
+#[derive(Clone, Copy)]
+struct BusyWait {
+    threshold_ns: u32,
+    cycles_per_ms: u32,
 }
 
+impl BusyWait {
+    fn cycles(&self, ns: u32) -> u32 {
+        (u64::from(ns) * u64::from(self.cycles_per_ms)).div_ceil(1_000_000) as u32
+    }
+}
+
+/// Timer window the cycle counter is calibrated on
+const CALIBRATION_US: u64 = 1_000;
+
+/// Longest busy wait, within the 24 bits of SysTick up to 300 MHz
+const MAX_BUSY_WAIT_NS: u32 = 50_000_000;
+
 impl<A: hal::timer::Alarm> AsyncAlarm<A> {
     fn new(alarm: A) -> Self {
-        Self { alarm }
+        Self {
+            alarm,
+            busy_wait: None,
+        }
+    }
+
+    /// Spin on the core cycle counter for delays up to `threshold_ns`
+    /// (at most 50 ms) instead of waiting for the alarm interrupt
+    ///
+    /// The counter is calibrated against the timer, taking about 1 ms, and
+    /// started on each core at its first busy wait. On rp2040 the counter is
+    /// SysTick, which is taken over on every core that busy waits.
+    #[allow(private_bounds)]
+    pub fn with_busy_wait(mut self, threshold_ns: u32) -> Self
+    where
+        A: AlarmCounter,
+    {
+        cycles::enable();
+
+        // start on a tick edge
+        let start = A::now();
+        while A::now() == start {}
+
+        let start = A::now();
+        let start_cycles = cycles::now();
+        while A::now() < start + MicrosDurationU64::from_ticks(CALIBRATION_US) {}
+        let cycles_per_ms = cycles::elapsed(start_cycles) * (1_000 / CALIBRATION_US) as u32;
+
+        defmt::debug!("busy wait calibrated at {} cycles per ms", cycles_per_ms);
+
+        self.busy_wait = Some(BusyWait {
+            threshold_ns: threshold_ns.min(MAX_BUSY_WAIT_NS),
+            cycles_per_ms,
+        });
+
+        self
+    }
+
+    /// Number of cycles to spin for when `ns` is below the busy wait
+    /// threshold
+    fn busy_wait_cycles(&self, ns: u32) -> Option<u32> {
+        self.busy_wait
+            .filter(|busy_wait| ns <= busy_wait.threshold_ns)
+            .map(|busy_wait| busy_wait.cycles(ns))
+    }
+
+    /// Share the alarm between any number of [`SharedDelay`], up to `N` of
+    /// them waiting at the same time
+    pub fn into_shared<const N: usize>(self) -> SharedAlarm<A, N> {
+        SharedAlarm::new(self.alarm)
+    }
+
+    /// Periodic ticker ticking every `period`, the first tick one period
+    /// from now
+    #[allow(private_bounds)]
+    pub fn into_ticker(self, period: MicrosDurationU32) -> AsyncTicker<A>
+    where
+        A: AlarmCounter,
+    {
+        let period = MicrosDurationU64::from_ticks(u64::from(period.ticks()));
+
+        AsyncTicker {
+            next: A::now() + period,
+            period,
+            alarm: self,
+        }
+    }
+
+    fn alarm_at(&mut self, deadline: Instant) -> AsyncAlarmFut<'_, A> {
+        AsyncAlarmFut {
+            alarm: &mut self.alarm,
+            deadline,
+            polled: false,
+            done: false,
+        }
+    }
+}
+
+#[allow(private_bounds)]
+impl<A> AsyncAlarm<A>
+where
+    A: hal::timer::Alarm + AlarmCounter + WakerRegister,
+{
+    /// Current value of the timer counter
+    pub fn now(&self) -> Instant {
+        A::now()
+    }
+
+    /// Wait until the timer counter reaches `deadline`
+    ///
+    /// The alarm matches at most `u32::MAX` microseconds ahead: further
+    /// deadlines are reached reprogramming it once per step.
+    pub async fn wait_until(&mut self, deadline: Instant) {
+        loop {
+            let now = A::now();
+            if now >= deadline {
+                break;
+            }
+
+            let step = (deadline - now).ticks().min(MAX_STEP_US);
+            self.alarm_at(now + MicrosDurationU64::from_ticks(step))
+                .await;
+        }
+    }
+
+    pub async fn delay_us_u64(&mut self, us: u64) {
+        let deadline = A::now() + MicrosDurationU64::from_ticks(us);
+        self.wait_until(deadline).await;
     }
 }
 
+/// Longest step of [`AsyncAlarm::wait_until`], below the `u32::MAX`
+/// microseconds accepted by `Alarm::schedule_at`
+const MAX_STEP_US: u64 = u32::MAX as u64 - 1_000;
+
 impl<A> DelayNs for AsyncAlarm<A>
 where
-    A: hal::timer::Alarm + WakerRegister,
+    A: hal::timer::Alarm + AlarmCounter + WakerRegister,
 {
     async fn delay_ns(&mut self, delay: u32) {
+        if let Some(cycles) = self.busy_wait_cycles(delay) {
+            cycles::spin(cycles);
+            return;
+        }
+
         let delay = delay.div_ceil(1_000);
         if delay > 0 {
-            AsyncAlarmFut {
-                alarm: &mut self.alarm,
-                delay,
-                polled: false,
-                done: false,
-            }
-            .await;
+            self.delay_us_u64(u64::from(delay)).await;
         }
     }
 }
 
-pub(crate) unsafe fn init() {
-    #[cfg(all(target_arch = "arm", feature = "rp235x"))]
-    {
-        cortex_m::peripheral::NVIC::unmask(hal::pac::interrupt::TIMER0_IRQ_0);
-        cortex_m::peripheral::NVIC::unmask(hal::pac::interrupt::TIMER0_IRQ_1);
-        cortex_m::peripheral::NVIC::unmask(hal::pac::interrupt::TIMER0_IRQ_2);
-        cortex_m::peripheral::NVIC::unmask(hal::pac::interrupt::TIMER0_IRQ_3);
-        cortex_m::peripheral::NVIC::unmask(hal::pac::interrupt::TIMER1_IRQ_0);
-        cortex_m::peripheral::NVIC::unmask(hal::pac::interrupt::TIMER1_IRQ_1);
-        cortex_m::peripheral::NVIC::unmask(hal::pac::interrupt::TIMER1_IRQ_2);
-        cortex_m::peripheral::NVIC::unmask(hal::pac::interrupt::TIMER1_IRQ_3);
+/// Busy waits on the timer counter, or on the cycle counter below the busy
+/// wait threshold, without touching the alarm
+impl<A> embedded_hal::delay::DelayNs for AsyncAlarm<A>
+where
+    A: hal::timer::Alarm + AlarmCounter,
+{
+    fn delay_ns(&mut self, delay: u32) {
+        if let Some(cycles) = self.busy_wait_cycles(delay) {
+            cycles::spin(cycles);
+            return;
+        }
+
+        let deadline = A::now() + MicrosDurationU64::from_ticks(u64::from(delay.div_ceil(1_000)));
+        while A::now() < deadline {
+            core::hint::spin_loop();
+        }
     }
+}
 
-    #[cfg(all(target_arch = "arm", feature = "rp2040"))]
-    {
-        cortex_m::peripheral::NVIC::unmask(hal::pac::interrupt::TIMER_IRQ_0);
-        cortex_m::peripheral::NVIC::unmask(hal::pac::interrupt::TIMER_IRQ_1);
-        cortex_m::peripheral::NVIC::unmask(hal::pac::interrupt::TIMER_IRQ_2);
-        cortex_m::peripheral::NVIC::unmask(hal::pac::interrupt::TIMER_IRQ_3);
+/// Ticks that elapsed before [`AsyncTicker::next`] was called
+#[derive(Clone, Copy, Debug, defmt::Format)]
+pub struct Overrun {
+    pub missed: u64,
+}
+
+/// Periodic ticker on a hardware alarm
+///
+/// Deadlines are `start + n * period`: the time spent by the task between
+/// two ticks does not move the following ones.
+pub struct AsyncTicker<A>
+where
+    A: hal::timer::Alarm,
+{
+    alarm: AsyncAlarm<A>,
+    period: MicrosDurationU64,
+    next: Instant,
+}
+
+#[allow(private_bounds)]
+impl<A> AsyncTicker<A>
+where
+    A: hal::timer::Alarm + AlarmCounter + WakerRegister,
+{
+    /// Wait for the next tick
+    ///
+    /// When the task comes back after the tick already elapsed, it returns
+    /// at once with the number of elapsed ticks and the ticker skips to the
+    /// first future deadline, keeping the phase.
+    ///
+    /// Dropped before the tick, the wait is not counted: the tick is reported
+    /// by the next call, as an overrun once elapsed.
+    pub async fn next(&mut self) -> Result<(), Overrun> {
+        let now = A::now();
+        if now < self.next {
+            self.alarm.alarm_at(self.next).await;
+
+            self.next += self.period;
+
+            Ok(())
+        } else {
+            let missed = (now - self.next).ticks() / self.period.ticks() + 1;
+            self.next += MicrosDurationU64::from_ticks(missed * self.period.ticks());
+
+            Err(Overrun { missed })
+        }
     }
 
-    #[cfg(target_arch = "riscv32")]
-    {
-        todo!();
+    /// Restart the ticks from now
+    pub fn reset(&mut self) {
+        self.next = A::now() + self.period;
+    }
+
+    pub fn free(self) -> AsyncAlarm<A> {
+        self.alarm
     }
 }
 
+pub(crate) unsafe fn init() {
+    init_core();
+}
+
+/// Alarm interrupts of the alarms of this module: the ones of the time driver
+/// are unmasked by the time driver, each on its own core
 #[cfg(feature = "rp235x")]
-mod inner {
+const ALARM_IRQS: &[hal::pac::Interrupt] = &[
+    #[cfg(not(feature = "time_driver-timer0"))]
+    hal::pac::Interrupt::TIMER0_IRQ_0,
+    #[cfg(not(feature = "time_driver-timer0"))]
+    hal::pac::Interrupt::TIMER0_IRQ_1,
+    hal::pac::Interrupt::TIMER0_IRQ_2,
+    hal::pac::Interrupt::TIMER0_IRQ_3,
+    #[cfg(any(not(feature = "time_driver"), feature = "time_driver-timer0"))]
+    hal::pac::Interrupt::TIMER1_IRQ_0,
+    #[cfg(any(not(feature = "time_driver"), feature = "time_driver-timer0"))]
+    hal::pac::Interrupt::TIMER1_IRQ_1,
+    hal::pac::Interrupt::TIMER1_IRQ_2,
+    hal::pac::Interrupt::TIMER1_IRQ_3,
+];
+
+/// Alarm interrupts of the alarms of this module: the ones of the time driver
+/// are unmasked by the time driver, each on its own core
+#[cfg(feature = "rp2040")]
+const ALARM_IRQS: &[hal::pac::Interrupt] = &[
+    #[cfg(not(feature = "time_driver"))]
+    hal::pac::Interrupt::TIMER_IRQ_0,
+    #[cfg(not(feature = "time_driver"))]
+    hal::pac::Interrupt::TIMER_IRQ_1,
+    hal::pac::Interrupt::TIMER_IRQ_2,
+    hal::pac::Interrupt::TIMER_IRQ_3,
+];
+
+/// Unmask the alarm interrupts on the calling core
+///
+/// An alarm interrupt unmasked on both cores is served by the first one to
+/// take it, the waiting task is woken on its own core in any case.
+pub(crate) unsafe fn init_core() {
+    for irq in ALARM_IRQS {
+        #[cfg(target_arch = "arm")]
+        cortex_m::peripheral::NVIC::unmask(*irq);
+
+        #[cfg(target_arch = "riscv32")]
+        crate::xh3irq::unmask(*irq);
+    }
+
+    #[cfg(target_arch = "riscv32")]
+    crate::xh3irq::enable();
+}
+
+#[cfg(feature = "rp235x")]
+pub(crate) mod inner {
     macro_rules! alarm_impl {
         (mod_name: $mod_name:ident, name: $alarm:ident, alarm_name: $alarm_name:ident, device: $device:ident, wakers: $wakers:ident, irq: $irq:ident, timer: $timer:ident, mask: $mask:expr) => {
             mod $mod_name {
                 use crate::{get_current_core, write_bitmask_clear};
+                use core::sync::atomic::{AtomicUsize, Ordering};
                 use core::task::Waker;
 
-                use crate::delay::{hal, AsyncAlarm, NUM_CORES};
+                use crate::delay::{hal, read_counter, AlarmCounter, AsyncAlarm, NUM_CORES};
 
                 use embassy_sync::waitqueue::AtomicWaker;
 
@@ -149,21 +432,46 @@ mod inner {
                 static $wakers: [AtomicWaker; NUM_CORES] =
                     [const { AtomicWaker::new() }; NUM_CORES];
 
+                /// Core of the task waiting on the alarm, whichever core takes
+                /// the interrupt
+                static OWNER: AtomicUsize = AtomicUsize::new(0);
+
                 impl WakerRegister for hal::timer::$alarm_name<hal::timer::$device> {
                     fn register_waker(waker: &Waker) {
-                        $wakers[get_current_core()].register(waker);
+                        let core = get_current_core();
+                        OWNER.store(core, Ordering::Relaxed);
+                        $wakers[core].register(waker);
                     }
                 }
 
+                impl AlarmCounter for hal::timer::$alarm_name<hal::timer::$device> {
+                    fn now() -> hal::timer::Instant {
+                        read_counter(unsafe { &*hal::pac::$timer::ptr() })
+                    }
+                }
+
+                #[cfg(target_arch = "riscv32")]
+                pub(in crate::delay) const EXTERNAL_INTERRUPT: (interrupt::Interrupt, fn()) =
+                    (interrupt::Interrupt::$irq, $irq);
+
                 #[cfg_attr(target_arch = "arm", interrupt)]
+                #[cfg_attr(target_arch = "riscv32", allow(non_snake_case))]
                 fn $irq() {
-                    $wakers[get_current_core()].wake();
+                    let timer = unsafe { hal::pac::$timer::steal() };
 
-                    unsafe {
-                        let timer = hal::pac::$timer::steal();
+                    // unmasked on both cores, the other one served it already
+                    if timer.ints().read().bits() & $mask == 0 {
+                        return;
+                    }
 
+                    $wakers[OWNER.load(Ordering::Relaxed)].wake();
+
+                    unsafe {
                         write_bitmask_clear(timer.inte().as_ptr(), $mask);
 
+                        // set by `schedule_at` for a deadline already elapsed
+                        write_bitmask_clear(timer.intf().as_ptr(), $mask);
+
                         timer
                             .intr()
                             .write_with_zero(|w| w.$alarm().clear_bit_by_one());
@@ -173,6 +481,24 @@ mod inner {
         };
     }
 
+    #[cfg(target_arch = "riscv32")]
+    pub(crate) const EXTERNAL_INTERRUPTS: &[(super::hal::pac::Interrupt, fn())] = &[
+        #[cfg(not(feature = "time_driver-timer0"))]
+        timer0_alarm0::EXTERNAL_INTERRUPT,
+        #[cfg(not(feature = "time_driver-timer0"))]
+        timer0_alarm1::EXTERNAL_INTERRUPT,
+        timer0_alarm2::EXTERNAL_INTERRUPT,
+        timer0_alarm3::EXTERNAL_INTERRUPT,
+        #[cfg(any(not(feature = "time_driver"), feature = "time_driver-timer0"))]
+        timer1_alarm0::EXTERNAL_INTERRUPT,
+        #[cfg(any(not(feature = "time_driver"), feature = "time_driver-timer0"))]
+        timer1_alarm1::EXTERNAL_INTERRUPT,
+        timer1_alarm2::EXTERNAL_INTERRUPT,
+        timer1_alarm3::EXTERNAL_INTERRUPT,
+    ];
+
+    // alarms 0 and 1 of the time driver timer belong to the time driver
+    #[cfg(not(feature = "time_driver-timer0"))]
     alarm_impl! {
         mod_name: timer0_alarm0,
         name: alarm_0,
@@ -184,6 +510,7 @@ mod inner {
         mask: 0b0001
     }
 
+    #[cfg(not(feature = "time_driver-timer0"))]
     alarm_impl! {
         mod_name: timer0_alarm1,
         name: alarm_1,
@@ -217,6 +544,7 @@ mod inner {
         mask: 0b1000
     }
 
+    #[cfg(any(not(feature = "time_driver"), feature = "time_driver-timer0"))]
     alarm_impl! {
         mod_name: timer1_alarm0,
         name: alarm_0,
@@ -228,6 +556,7 @@ mod inner {
         mask: 0b0001
     }
 
+    #[cfg(any(not(feature = "time_driver"), feature = "time_driver-timer0"))]
     alarm_impl! {
         mod_name: timer1_alarm1,
         name: alarm_1,
@@ -268,9 +597,10 @@ mod inner {
         (mod_name: $mod_name:ident, name: $alarm:ident, alarm_name: $alarm_name:ident, wakers: $wakers:ident, irq: $irq:ident, timer: $timer:ident, mask: $mask:expr) => {
             mod $mod_name {
                 use crate::{get_current_core, write_bitmask_clear};
+                use core::sync::atomic::{AtomicUsize, Ordering};
                 use core::task::Waker;
 
-                use crate::delay::{hal, AsyncAlarm, NUM_CORES};
+                use crate::delay::{hal, read_counter, AlarmCounter, AsyncAlarm, NUM_CORES};
 
                 use embassy_sync::waitqueue::AtomicWaker;
 
@@ -289,21 +619,41 @@ mod inner {
                 static $wakers: [AtomicWaker; NUM_CORES] =
                     [const { AtomicWaker::new() }; NUM_CORES];
 
+                /// Core of the task waiting on the alarm, whichever core takes
+                /// the interrupt
+                static OWNER: AtomicUsize = AtomicUsize::new(0);
+
                 impl WakerRegister for hal::timer::$alarm_name {
                     fn register_waker(waker: &Waker) {
-                        $wakers[get_current_core()].register(waker);
+                        let core = get_current_core();
+                        OWNER.store(core, Ordering::Relaxed);
+                        $wakers[core].register(waker);
+                    }
+                }
+
+                impl AlarmCounter for hal::timer::$alarm_name {
+                    fn now() -> hal::timer::Instant {
+                        read_counter(unsafe { &*hal::pac::$timer::ptr() })
                     }
                 }
 
                 #[cfg_attr(target_arch = "arm", interrupt)]
                 fn $irq() {
-                    $wakers[get_current_core()].wake();
+                    let timer = unsafe { hal::pac::$timer::steal() };
 
-                    unsafe {
-                        let timer = hal::pac::$timer::steal();
+                    // unmasked on both cores, the other one served it already
+                    if timer.ints().read().bits() & $mask == 0 {
+                        return;
+                    }
 
+                    $wakers[OWNER.load(Ordering::Relaxed)].wake();
+
+                    unsafe {
                         write_bitmask_clear(timer.inte().as_ptr(), $mask);
 
+                        // set by `schedule_at` for a deadline already elapsed
+                        write_bitmask_clear(timer.intf().as_ptr(), $mask);
+
                         timer
                             .intr()
                             .write_with_zero(|w| w.$alarm().clear_bit_by_one());
@@ -313,6 +663,8 @@ mod inner {
         };
     }
 
+    // alarms 0 and 1 belong to the time driver
+    #[cfg(not(feature = "time_driver"))]
     alarm_impl! {
         mod_name: timer_alarm0,
         name: alarm_0,
@@ -323,6 +675,7 @@ mod inner {
         mask: 0b0001
     }
 
+    #[cfg(not(feature = "time_driver"))]
     alarm_impl! {
         mod_name: timer_alarm1,
         name: alarm_1,
@@ -0,0 +1,88 @@
+//! Core cycle counter for the short busy waits
+//!
+//! The DWT cycle counter on Cortex-M33, SysTick on Cortex-M0+ and `mcycle`
+//! on Hazard3. The value only increases, modulo [`MASK`] + 1.
+//!
+//! Each core has its own counter: it is started by the first busy wait on
+//! the core, whichever core calibrated it. On rp2040 SysTick is taken over,
+//! not to be used by the application on a core that busy waits.
+
+#[cfg(all(target_arch = "arm", feature = "rp235x"))]
+pub(super) const MASK: u32 = u32::MAX;
+
+#[cfg(all(target_arch = "arm", feature = "rp235x"))]
+pub(super) fn enable() {
+    let mut cp = unsafe { cortex_m::Peripherals::steal() };
+    cp.DCB.enable_trace();
+    cp.DWT.enable_cycle_counter();
+}
+
+#[cfg(all(target_arch = "arm", feature = "rp235x"))]
+fn enabled() -> bool {
+    cortex_m::peripheral::DWT::cycle_counter_enabled()
+}
+
+#[cfg(all(target_arch = "arm", feature = "rp235x"))]
+pub(super) fn now() -> u32 {
+    cortex_m::peripheral::DWT::cycle_count()
+}
+
+/// SysTick is taken over, running from the core clock
+#[cfg(feature = "rp2040")]
+pub(super) const MASK: u32 = 0x00ff_ffff;
+
+#[cfg(feature = "rp2040")]
+pub(super) fn enable() {
+    use cortex_m::peripheral::syst::SystClkSource;
+
+    let mut syst = unsafe { cortex_m::Peripherals::steal() }.SYST;
+    syst.set_clock_source(SystClkSource::Core);
+    syst.set_reload(MASK);
+    syst.clear_current();
+    syst.enable_counter();
+}
+
+#[cfg(feature = "rp2040")]
+fn enabled() -> bool {
+    let mut syst = unsafe { cortex_m::Peripherals::steal() }.SYST;
+    syst.is_counter_enabled() && cortex_m::peripheral::SYST::get_reload() == MASK
+}
+
+#[cfg(feature = "rp2040")]
+pub(super) fn now() -> u32 {
+    // SysTick counts down
+    MASK - cortex_m::peripheral::SYST::get_current()
+}
+
+#[cfg(target_arch = "riscv32")]
+pub(super) const MASK: u32 = u32::MAX;
+
+#[cfg(target_arch = "riscv32")]
+pub(super) fn enable() {
+    unsafe { riscv::register::mcountinhibit::clear_cy() };
+}
+
+#[cfg(target_arch = "riscv32")]
+fn enabled() -> bool {
+    !riscv::register::mcountinhibit::read().cy()
+}
+
+#[cfg(target_arch = "riscv32")]
+pub(super) fn now() -> u32 {
+    riscv::register::mcycle::read() as u32
+}
+
+pub(super) fn elapsed(start: u32) -> u32 {
+    now().wrapping_sub(start) & MASK
+}
+
+pub(super) fn spin(cycles: u32) {
+    if !enabled() {
+        enable();
+    }
+
+    let start = now();
+    while elapsed(start) < cycles {
+        core::hint::spin_loop();
+    }
+}
@@ -0,0 +1,192 @@
+use core::cell::RefCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use critical_section::Mutex;
+
+use embedded_hal_async::delay::DelayNs;
+
+use fugit::MicrosDurationU64;
+
+use crate::WakerRegister;
+
+use super::{arm, disarm, hal, AlarmCounter, Instant, MAX_STEP_US};
+
+struct Slot {
+    deadline: Instant,
+    waker: Waker,
+}
+
+struct SharedAlarmState<A, const N: usize> {
+    alarm: A,
+    slots: [Option<Slot>; N],
+}
+
+impl<A, const N: usize> SharedAlarmState<A, N>
+where
+    A: hal::timer::Alarm + AlarmCounter + WakerRegister,
+{
+    /// Wake the expired waiters and arm the alarm for the earliest of the
+    /// others: the alarm interrupt wakes that one only, which then wakes the
+    /// following ones from here
+    fn update(&mut self, now: Instant) {
+        let mut next: Option<&Slot> = None;
+
+        for slot in self.slots.iter().flatten() {
+            if slot.deadline <= now {
+                slot.waker.wake_by_ref();
+            } else if next.is_none_or(|next| slot.deadline < next.deadline) {
+                next = Some(slot);
+            }
+        }
+
+        match next {
+            Some(slot) => {
+                A::register_waker(&slot.waker);
+
+                let step = (slot.deadline - now).ticks().min(MAX_STEP_US);
+                arm(&mut self.alarm, now + MicrosDurationU64::from_ticks(step));
+            }
+            None => disarm(&mut self.alarm),
+        }
+    }
+}
+
+/// One hardware alarm shared by any number of [`SharedDelay`], up to `N`
+/// of them waiting at the same time
+///
+/// A delay that finds no free slot polls again at once until one is freed,
+/// busy waiting through the executor.
+pub struct SharedAlarm<A, const N: usize = 8> {
+    state: Mutex<RefCell<SharedAlarmState<A, N>>>,
+}
+
+impl<A, const N: usize> SharedAlarm<A, N> {
+    pub(super) fn new(alarm: A) -> Self {
+        Self {
+            state: Mutex::new(RefCell::new(SharedAlarmState {
+                alarm,
+                slots: [const { None }; N],
+            })),
+        }
+    }
+
+    pub fn delay(&self) -> SharedDelay<'_, A, N> {
+        SharedDelay { shared: self }
+    }
+}
+
+/// Cloneable async delay on a [`SharedAlarm`]
+pub struct SharedDelay<'a, A, const N: usize = 8> {
+    shared: &'a SharedAlarm<A, N>,
+}
+
+impl<A, const N: usize> Clone for SharedDelay<'_, A, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A, const N: usize> Copy for SharedDelay<'_, A, N> {}
+
+impl<A, const N: usize> DelayNs for SharedDelay<'_, A, N>
+where
+    A: hal::timer::Alarm + AlarmCounter + WakerRegister,
+{
+    async fn delay_ns(&mut self, delay: u32) {
+        let delay = delay.div_ceil(1_000);
+        if delay > 0 {
+            SharedDelayFut {
+                shared: self.shared,
+                deadline: A::now() + MicrosDurationU64::from_ticks(u64::from(delay)),
+                slot: None,
+            }
+            .await;
+        }
+    }
+}
+
+struct SharedDelayFut<'a, A, const N: usize>
+where
+    A: hal::timer::Alarm + AlarmCounter + WakerRegister,
+{
+    shared: &'a SharedAlarm<A, N>,
+    deadline: Instant,
+    slot: Option<usize>,
+}
+
+impl<A, const N: usize> SharedDelayFut<'_, A, N>
+where
+    A: hal::timer::Alarm + AlarmCounter + WakerRegister,
+{
+    fn release(&mut self, state: &mut SharedAlarmState<A, N>) {
+        if let Some(slot) = self.slot.take() {
+            state.slots[slot] = None;
+        }
+    }
+}
+
+impl<A, const N: usize> Drop for SharedDelayFut<'_, A, N>
+where
+    A: hal::timer::Alarm + AlarmCounter + WakerRegister,
+{
+    fn drop(&mut self) {
+        if self.slot.is_some() {
+            critical_section::with(|cs| {
+                let mut state = self.shared.state.borrow_ref_mut(cs);
+                self.release(&mut state);
+                state.update(A::now());
+            });
+        }
+    }
+}
+
+impl<A, const N: usize> Future for SharedDelayFut<'_, A, N>
+where
+    A: hal::timer::Alarm + AlarmCounter + WakerRegister,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        critical_section::with(|cs| {
+            let mut state = this.shared.state.borrow_ref_mut(cs);
+            let now = A::now();
+
+            let result = if now >= this.deadline {
+                this.release(&mut state);
+
+                Poll::Ready(())
+            } else {
+                match this.slot {
+                    Some(slot) => {
+                        if let Some(slot) = state.slots[slot].as_mut() {
+                            slot.waker.clone_from(cx.waker());
+                        }
+                    }
+                    None => {
+                        this.slot = state.slots.iter().position(Option::is_none);
+
+                        match this.slot {
+                            Some(slot) => {
+                                state.slots[slot] = Some(Slot {
+                                    deadline: this.deadline,
+                                    waker: cx.waker().clone(),
+                                });
+                            }
+                            None => cx.waker().wake_by_ref(),
+                        }
+                    }
+                }
+
+                Poll::Pending
+            };
+
+            state.update(now);
+
+            result
+        })
+    }
+}
@@ -1,10 +1,18 @@
-mod wait_for_any_edge;
+mod edge_counter;
+mod flex_pin;
+mod output_pin;
+mod pin_wait;
+mod wait_for_any;
 mod wait_for_edge;
 mod wait_for_level;
 
-use core::marker::PhantomData;
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicBool, Ordering};
 
-use embassy_sync::waitqueue::AtomicWaker;
+use embassy_sync::blocking_mutex::{raw::CriticalSectionRawMutex, Mutex};
+use embassy_sync::waitqueue::MultiWakerRegistration;
+
+use portable_atomic::AtomicU32;
 
 #[cfg(target_arch = "riscv32")]
 compile_error!("TODO: riscv32");
@@ -22,8 +30,17 @@ use embedded_hal_async::digital::Wait;
 
 use crate::{get_current_core, write_bitmask_clear, IntoAsync, WakerRegister, NUM_CORES};
 
+use core::task::Waker;
+
+pub use edge_counter::{Edge, EdgeCounter};
+pub use flex_pin::AsyncFlexPin;
+pub use output_pin::AsyncOutputPin;
+pub use wait_for_any::{wait_for_any, PinEvent};
+
 type GpioInputPin<I, P> = hal::gpio::Pin<I, hal::gpio::FunctionSio<hal::gpio::SioInput>, P>;
 
+type GpioOutputPin<I, P> = hal::gpio::Pin<I, hal::gpio::FunctionSio<hal::gpio::SioOutput>, P>;
+
 pub struct AsyncInputPin<I: hal::gpio::PinId, P: hal::gpio::PullType> {
     pin: GpioInputPin<I, P>,
 }
@@ -46,56 +63,103 @@ impl<I: hal::gpio::PinId, P: hal::gpio::PullType> AsyncInputPin<I, P> {
     fn new(pin: GpioInputPin<I, P>) -> Self {
         Self { pin }
     }
+
+    /// Runtime id of the pin
+    pub fn id(&self) -> hal::gpio::DynPinId {
+        self.pin.id()
+    }
+
+    /// Erase the pin id and pull type: pins of any type then fit in one
+    /// array, as [`wait_for_any`] takes
+    pub fn into_dyn(self) -> AsyncInputPin<hal::gpio::DynPinId, hal::gpio::DynPullType> {
+        AsyncInputPin::new(self.pin.into_dyn_pin().into_pull_type())
+    }
+
+    /// Keep the interrupt for `edge` armed and count the edges until the
+    /// counter is dropped
+    #[allow(private_bounds)]
+    pub fn edge_counter(&mut self, edge: Edge) -> EdgeCounter<'_, I, P>
+    where
+        I: PinWaker,
+    {
+        EdgeCounter::new(&mut self.pin, edge)
+    }
+
+    /// Handle for waiting on the pin from several tasks at a time
+    pub fn watcher(&self) -> PinWatcher<'_, I, P> {
+        PinWatcher { pin: &self.pin }
+    }
 }
 
-impl<I: hal::gpio::PinId + WakerRegister, P: hal::gpio::PullType> Wait for AsyncInputPin<I, P> {
+impl<I: hal::gpio::PinId + PinWaker, P: hal::gpio::PullType> Wait for AsyncInputPin<I, P> {
     async fn wait_for_high(&mut self) -> Result<(), <Self as ErrorType>::Error> {
-        wait_for_level::WaitForLevel {
-            pin: &mut self.pin,
-            polled: false,
-            done: false,
-            _info: PhantomData::<wait_for_level::HighLevelInfo>,
-        }
-        .await
+        self.watcher().wait_for_high().await
     }
 
     async fn wait_for_low(&mut self) -> Result<(), <Self as ErrorType>::Error> {
-        wait_for_level::WaitForLevel {
-            pin: &mut self.pin,
-            polled: false,
-            done: false,
-            _info: PhantomData::<wait_for_level::LowLevelInfo>,
-        }
-        .await
+        self.watcher().wait_for_low().await
     }
 
     async fn wait_for_rising_edge(&mut self) -> Result<(), <Self as ErrorType>::Error> {
-        wait_for_edge::WaitForEdge {
-            pin: &mut self.pin,
-            polled: false,
-            done: false,
-            _info: PhantomData::<wait_for_edge::EdgeHighInfo>,
-        }
-        .await
+        self.watcher().wait_for_rising_edge().await
     }
 
     async fn wait_for_falling_edge(&mut self) -> Result<(), <Self as ErrorType>::Error> {
-        wait_for_edge::WaitForEdge {
-            pin: &mut self.pin,
-            polled: false,
-            done: false,
-            _info: PhantomData::<wait_for_edge::EdgeLowInfo>,
-        }
-        .await
+        self.watcher().wait_for_falling_edge().await
     }
 
     async fn wait_for_any_edge(&mut self) -> Result<(), <Self as ErrorType>::Error> {
-        wait_for_any_edge::WaitForAnyEdge {
-            pin: &mut self.pin,
-            polled: false,
-            done: false,
-        }
-        .await
+        self.watcher().wait_for_any_edge().await
+    }
+}
+
+/// Cloneable handle on an [`AsyncInputPin`]: each copy waits on its own,
+/// any number of tasks at a time
+pub struct PinWatcher<'a, I: hal::gpio::PinId, P: hal::gpio::PullType> {
+    pin: &'a GpioInputPin<I, P>,
+}
+
+impl<I: hal::gpio::PinId, P: hal::gpio::PullType> Clone for PinWatcher<'_, I, P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<I: hal::gpio::PinId, P: hal::gpio::PullType> Copy for PinWatcher<'_, I, P> {}
+
+impl<I: hal::gpio::PinId, P: hal::gpio::PullType> ErrorType for PinWatcher<'_, I, P> {
+    type Error = <GpioInputPin<I, P> as ErrorType>::Error;
+}
+
+impl<I: hal::gpio::PinId, P: hal::gpio::PullType> InputPin for PinWatcher<'_, I, P> {
+    fn is_high(&mut self) -> Result<bool, <Self as ErrorType>::Error> {
+        self.pin.as_input().is_high()
+    }
+
+    fn is_low(&mut self) -> Result<bool, <Self as ErrorType>::Error> {
+        self.pin.as_input().is_low()
+    }
+}
+
+impl<I: hal::gpio::PinId + PinWaker, P: hal::gpio::PullType> Wait for PinWatcher<'_, I, P> {
+    async fn wait_for_high(&mut self) -> Result<(), <Self as ErrorType>::Error> {
+        wait_for_level::wait_for_high(self.pin).await
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), <Self as ErrorType>::Error> {
+        wait_for_level::wait_for_low(self.pin).await
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), <Self as ErrorType>::Error> {
+        wait_for_edge::wait_for_rising_edge(self.pin).await
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), <Self as ErrorType>::Error> {
+        wait_for_edge::wait_for_falling_edge(self.pin).await
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), <Self as ErrorType>::Error> {
+        wait_for_edge::wait_for_any_edge(self.pin).await
     }
 }
 
@@ -109,15 +173,114 @@ impl<I: hal::gpio::PinId, P: hal::gpio::PullType> IntoAsync
     }
 }
 
+/// Type-erased pins are switched to SIO input, given back if the function is
+/// not valid for the pin
+impl<P: hal::gpio::PullType> IntoAsync
+    for hal::gpio::Pin<hal::gpio::DynPinId, hal::gpio::DynFunction, P>
+{
+    type Target = Result<AsyncInputPin<hal::gpio::DynPinId, P>, Self>;
+
+    fn into_async(self) -> Self::Target {
+        self.try_into_function().map(AsyncInputPin::new)
+    }
+}
+
+/// Waker of a pin, chosen at compile time or looked up from the runtime id
+pub(crate) trait PinWaker {
+    fn register_pin_waker(id: hal::gpio::DynPinId, waker: &Waker);
+}
+
+impl<I: WakerRegister> PinWaker for I {
+    fn register_pin_waker(_id: hal::gpio::DynPinId, waker: &Waker) {
+        I::register_waker(waker);
+    }
+}
+
 const NUM_PINS: usize = 48;
 
-static WAKERS_BANK0: [[AtomicWaker; NUM_PINS]; NUM_CORES] =
-    [const { [const { AtomicWaker::new() }; NUM_PINS] }; NUM_CORES];
+/// USB DP and DM come first on rp235x
+#[cfg(feature = "rp235x")]
+const NUM_PINS_QSPI: usize = 8;
+
+#[cfg(feature = "rp2040")]
+const NUM_PINS_QSPI: usize = 6;
+
+/// Tasks waiting on one pin at the same time, on each core: one more wakes
+/// them all to make room, the others register again when polled
+const MAX_WAITERS: usize = 4;
+
+/// Tasks waiting on a pin, all woken by an interrupt
+struct PinWakers(Mutex<CriticalSectionRawMutex, RefCell<MultiWakerRegistration<MAX_WAITERS>>>);
+
+impl PinWakers {
+    const fn new() -> Self {
+        Self(Mutex::new(RefCell::new(MultiWakerRegistration::new())))
+    }
+
+    fn register(&self, waker: &Waker) {
+        self.0.lock(|wakers| wakers.borrow_mut().register(waker));
+    }
+
+    fn wake(&self) {
+        self.0.lock(|wakers| wakers.borrow_mut().wake());
+    }
+}
+
+static WAKERS_BANK0: [[PinWakers; NUM_PINS]; NUM_CORES] =
+    [const { [const { PinWakers::new() }; NUM_PINS] }; NUM_CORES];
+
+static WAKERS_QSPI: [[PinWakers; NUM_PINS_QSPI]; NUM_CORES] =
+    [const { [const { PinWakers::new() }; NUM_PINS_QSPI] }; NUM_CORES];
+
+/// Interrupt state of a pin shared with the interrupt handler
+struct PinState {
+    /// Events seen by the handler for each interrupt kind, wrapping
+    fired: [AtomicU32; 4],
+    /// Futures waiting for each interrupt kind
+    waiting: [AtomicU32; 4],
+    /// Interrupts kept enabled by an [`EdgeCounter`]
+    armed: AtomicBool,
+    /// Edges counted while armed
+    count: AtomicU32,
+}
+
+impl PinState {
+    const fn new() -> Self {
+        Self {
+            fired: [const { AtomicU32::new(0) }; 4],
+            waiting: [const { AtomicU32::new(0) }; 4],
+            armed: AtomicBool::new(false),
+            count: AtomicU32::new(0),
+        }
+    }
+}
+
+static PINS_BANK0: [PinState; NUM_PINS] = [const { PinState::new() }; NUM_PINS];
+
+static PINS_QSPI: [PinState; NUM_PINS_QSPI] = [const { PinState::new() }; NUM_PINS_QSPI];
+
+fn pin_state(id: hal::gpio::DynPinId) -> &'static PinState {
+    match id.bank {
+        hal::gpio::DynBankId::Bank0 => &PINS_BANK0[usize::from(id.num)],
+        hal::gpio::DynBankId::Qspi => &PINS_QSPI[usize::from(id.num) % 8],
+    }
+}
+
+/// Bit of `interrupt` in the 4 bits of a pin
+const fn interrupt_bit(interrupt: hal::gpio::Interrupt) -> usize {
+    match interrupt {
+        hal::gpio::Interrupt::LevelLow => 0,
+        hal::gpio::Interrupt::LevelHigh => 1,
+        hal::gpio::Interrupt::EdgeLow => 2,
+        hal::gpio::Interrupt::EdgeHigh => 3,
+    }
+}
 
 pub(crate) unsafe fn init() {
     #[cfg(target_arch = "arm")]
     {
         cortex_m::peripheral::NVIC::unmask(hal::pac::interrupt::IO_IRQ_BANK0);
+        cortex_m::peripheral::NVIC::unmask(hal::pac::interrupt::IO_IRQ_QSPI);
     }
 
     #[cfg(target_arch = "riscv32")]
@@ -126,62 +289,267 @@ pub(crate) unsafe fn init() {
     }
 }
 
+/// Disable, record and clear the events of the pins of one interrupt
+/// register, 4 bits per pin, and wake their tasks
+///
+/// Armed pins keep their interrupts enabled and count the edges instead: an
+/// edge is lost only if another one of the same kind comes before the
+/// handler clears the first.
+unsafe fn wake_pins(
+    ints: u32,
+    inte: *mut u32,
+    intr: *mut u32,
+    wakers: &[PinWakers],
+    states: &[PinState],
+) {
+    for (k, (waker, state)) in wakers.iter().zip(states).enumerate() {
+        let event = (ints >> (k * 4)) & 0xf;
+        if event != 0 {
+            if state.armed.load(Ordering::Relaxed) {
+                // EdgeLow and EdgeHigh are the upper two bits
+                state
+                    .count
+                    .fetch_add((event >> 2).count_ones(), Ordering::Relaxed);
+            } else {
+                for (bit, fired) in state.fired.iter().enumerate() {
+                    if event & (1 << bit) != 0 {
+                        fired.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+                unsafe { write_bitmask_clear(inte, 0xf << (k * 4)) };
+            }
+            unsafe { write_bitmask_clear(intr, 0xf << (k * 4)) };
+            waker.wake();
+        }
+    }
+}
+
 #[cfg_attr(target_arch = "arm", interrupt)]
 fn IO_IRQ_BANK0() {
     let core = get_current_core();
 
-    let wakers = &WAKERS_BANK0[core];
+    let wakers = WAKERS_BANK0[core].chunks(8).zip(PINS_BANK0.chunks(8));
 
     let bank0 = unsafe { hal::pac::IO_BANK0::steal() };
     if core == 0 {
-        for (i, ints) in bank0.proc0_ints_iter().enumerate() {
-            for k in 0..8 {
-                let event = (ints.read().bits() >> (k * 4)) & 0xf;
-                if event != 0 {
-                    unsafe { write_bitmask_clear(bank0.proc0_inte(i).as_ptr(), 0xf << (k * 4)) };
-                    unsafe { write_bitmask_clear(bank0.intr(i).as_ptr(), 0xf << (k * 4)) };
-                    wakers[i * 8 + k].wake();
-                }
-            }
+        for ((i, ints), (wakers, states)) in bank0.proc0_ints_iter().enumerate().zip(wakers) {
+            unsafe {
+                wake_pins(
+                    ints.read().bits(),
+                    bank0.proc0_inte(i).as_ptr(),
+                    bank0.intr(i).as_ptr(),
+                    wakers,
+                    states,
+                )
+            };
         }
     } else if core == 1 {
-        for (i, ints) in bank0.proc1_ints_iter().enumerate() {
-            for k in 0..8 {
-                let event = (ints.read().bits() >> (k * 4)) & 0xf;
-                if event != 0 {
-                    unsafe { write_bitmask_clear(bank0.proc1_inte(i).as_ptr(), 0xf << (k * 4)) };
-                    unsafe { write_bitmask_clear(bank0.intr(i).as_ptr(), 0xf << (k * 4)) };
-                    wakers[i * 8 + k].wake();
-                }
-            }
+        for ((i, ints), (wakers, states)) in bank0.proc1_ints_iter().enumerate().zip(wakers) {
+            unsafe {
+                wake_pins(
+                    ints.read().bits(),
+                    bank0.proc1_inte(i).as_ptr(),
+                    bank0.intr(i).as_ptr(),
+                    wakers,
+                    states,
+                )
+            };
         }
     } else {
         defmt::error!("invalid core {}", core);
     }
 }
 
+#[cfg_attr(target_arch = "arm", interrupt)]
+fn IO_IRQ_QSPI() {
+    let core = get_current_core();
+
+    let wakers = &WAKERS_QSPI[core];
+
+    let qspi = unsafe { hal::pac::IO_QSPI::steal() };
+    if core == 0 {
+        unsafe {
+            wake_pins(
+                qspi.proc0_ints().read().bits(),
+                qspi.proc0_inte().as_ptr(),
+                qspi.intr().as_ptr(),
+                wakers,
+                &PINS_QSPI,
+            )
+        };
+    } else if core == 1 {
+        unsafe {
+            wake_pins(
+                qspi.proc1_ints().read().bits(),
+                qspi.proc1_inte().as_ptr(),
+                qspi.intr().as_ptr(),
+                wakers,
+                &PINS_QSPI,
+            )
+        };
+    } else {
+        defmt::error!("invalid core {}", core);
+    }
+}
+
 mod inner {
     use core::task::Waker;
 
     use crate::{get_current_core, WakerRegister};
 
-    use super::{hal, WAKERS_BANK0};
+    use super::{hal, PinWaker, WAKERS_BANK0, WAKERS_QSPI};
 
     macro_rules! pins_impl {
-        ($($gpio:ident => $e:expr),+) => {
+        ($bank:ident, $wakers:ident: $($gpio:ident => $e:expr),+) => {
             $(
-                impl WakerRegister for hal::gpio::bank0::$gpio {
+                impl WakerRegister for hal::gpio::$bank::$gpio {
                     fn register_waker(waker: &Waker) {
-                        WAKERS_BANK0[get_current_core()][$e].register(waker);
+                        $wakers[get_current_core()][$e].register(waker);
                     }
                 }
             )+
         }
     }
 
+    impl PinWaker for hal::gpio::DynPinId {
+        fn register_pin_waker(id: hal::gpio::DynPinId, waker: &Waker) {
+            let wakers = match id.bank {
+                hal::gpio::DynBankId::Bank0 => &WAKERS_BANK0[get_current_core()][..],
+                hal::gpio::DynBankId::Qspi => &WAKERS_QSPI[get_current_core()][..],
+            };
+
+            // QSPI pins are numbered from 56 on rp235x: the slot is the one
+            // of the interrupt registers, the number modulo 8
+            wakers[usize::from(id.num) % 8].register(waker);
+        }
+    }
+
     pins_impl! {
+        bank0, WAKERS_BANK0:
         Gpio0 => 0,
         Gpio1 => 1,
-        Gpio26 => 26
+        Gpio2 => 2,
+        Gpio3 => 3,
+        Gpio4 => 4,
+        Gpio5 => 5,
+        Gpio6 => 6,
+        Gpio7 => 7,
+        Gpio8 => 8,
+        Gpio9 => 9,
+        Gpio10 => 10,
+        Gpio11 => 11,
+        Gpio12 => 12,
+        Gpio13 => 13,
+        Gpio14 => 14,
+        Gpio15 => 15,
+        Gpio16 => 16,
+        Gpio17 => 17,
+        Gpio18 => 18,
+        Gpio19 => 19,
+        Gpio20 => 20,
+        Gpio21 => 21,
+        Gpio22 => 22,
+        Gpio23 => 23,
+        Gpio24 => 24,
+        Gpio25 => 25,
+        Gpio26 => 26,
+        Gpio27 => 27,
+        Gpio28 => 28,
+        Gpio29 => 29
+    }
+
+    #[cfg(feature = "rp235x")]
+    pins_impl! {
+        bank0, WAKERS_BANK0:
+        Gpio30 => 30,
+        Gpio31 => 31,
+        Gpio32 => 32,
+        Gpio33 => 33,
+        Gpio34 => 34,
+        Gpio35 => 35,
+        Gpio36 => 36,
+        Gpio37 => 37,
+        Gpio38 => 38,
+        Gpio39 => 39,
+        Gpio40 => 40,
+        Gpio41 => 41,
+        Gpio42 => 42,
+        Gpio43 => 43,
+        Gpio44 => 44,
+        Gpio45 => 45,
+        Gpio46 => 46,
+        Gpio47 => 47
+    }
+
+    #[cfg(feature = "rp235x")]
+    pins_impl! {
+        qspi, WAKERS_QSPI:
+        QspiUsbDp => 0,
+        QspiUsbDm => 1,
+        QspiSclk => 2,
+        QspiSs => 3,
+        QspiSd0 => 4,
+        QspiSd1 => 5,
+        QspiSd2 => 6,
+        QspiSd3 => 7
+    }
+
+    #[cfg(feature = "rp2040")]
+    pins_impl! {
+        qspi, WAKERS_QSPI:
+        QspiSclk => 0,
+        QspiSs => 1,
+        QspiSd0 => 2,
+        QspiSd1 => 3,
+        QspiSd2 => 4,
+        QspiSd3 => 5
     }
 }
+
+// every pin converts to an `AsyncInputPin` implementing `Wait`: checked by
+// the build, apart from the list of `inner`, with no hardware involved
+const _: () = {
+    const fn assert_wait<I: hal::gpio::PinId>()
+    where
+        hal::gpio::Pin<I, hal::gpio::FunctionSioInput, hal::gpio::PullUp>:
+            IntoAsync<Target = AsyncInputPin<I, hal::gpio::PullUp>>,
+        AsyncInputPin<I, hal::gpio::PullUp>: Wait,
+    {
+    }
+
+    macro_rules! assert_wait {
+        ($bank:ident: $($gpio:ident),+) => {
+            $(
+                assert_wait::<hal::gpio::$bank::$gpio>();
+            )+
+        };
+    }
+
+    assert_wait! {
+        bank0:
+        Gpio0, Gpio1, Gpio2, Gpio3, Gpio4, Gpio5, Gpio6, Gpio7, Gpio8, Gpio9,
+        Gpio10, Gpio11, Gpio12, Gpio13, Gpio14, Gpio15, Gpio16, Gpio17, Gpio18, Gpio19,
+        Gpio20, Gpio21, Gpio22, Gpio23, Gpio24, Gpio25, Gpio26, Gpio27, Gpio28, Gpio29
+    }
+
+    #[cfg(feature = "rp235x")]
+    assert_wait! {
+        bank0:
+        Gpio30, Gpio31, Gpio32, Gpio33, Gpio34, Gpio35, Gpio36, Gpio37, Gpio38, Gpio39,
+        Gpio40, Gpio41, Gpio42, Gpio43, Gpio44, Gpio45, Gpio46, Gpio47
+    }
+
+    #[cfg(feature = "rp235x")]
+    assert_wait! {
+        qspi:
+        QspiUsbDp, QspiUsbDm, QspiSclk, QspiSs, QspiSd0, QspiSd1, QspiSd2, QspiSd3
+    }
+
+    #[cfg(feature = "rp2040")]
+    assert_wait! {
+        qspi:
+        QspiSclk, QspiSs, QspiSd0, QspiSd1, QspiSd2, QspiSd3
+    }
+
+    assert_wait::<hal::gpio::DynPinId>();
+};
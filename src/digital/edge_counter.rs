@@ -0,0 +1,97 @@
+use core::future::poll_fn;
+use core::sync::atomic::Ordering;
+use core::task::Poll;
+
+use super::{hal, pin_state, GpioInputPin, PinWaker};
+
+/// Edges counted by an [`EdgeCounter`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum Edge {
+    Rising,
+    Falling,
+    Any,
+}
+
+impl Edge {
+    fn interrupts(self) -> &'static [hal::gpio::Interrupt] {
+        match self {
+            Edge::Rising => &[hal::gpio::Interrupt::EdgeHigh],
+            Edge::Falling => &[hal::gpio::Interrupt::EdgeLow],
+            Edge::Any => &[
+                hal::gpio::Interrupt::EdgeHigh,
+                hal::gpio::Interrupt::EdgeLow,
+            ],
+        }
+    }
+}
+
+/// Edge interrupt of a pin kept armed between awaits
+///
+/// The interrupt handler counts the edges while the task is busy elsewhere,
+/// [`EdgeCounter::next`] reports them all at once.
+pub struct EdgeCounter<'a, I, P>
+where
+    I: hal::gpio::PinId,
+    P: hal::gpio::PullType,
+{
+    pin: &'a mut GpioInputPin<I, P>,
+    edge: Edge,
+}
+
+#[allow(private_bounds)]
+impl<'a, I, P> EdgeCounter<'a, I, P>
+where
+    I: hal::gpio::PinId + PinWaker,
+    P: hal::gpio::PullType,
+{
+    pub(super) fn new(pin: &'a mut GpioInputPin<I, P>, edge: Edge) -> Self {
+        let state = pin_state(pin.id());
+        state.count.store(0, Ordering::Relaxed);
+        state.armed.store(true, Ordering::Relaxed);
+
+        for &interrupt in edge.interrupts() {
+            pin.clear_interrupt(interrupt);
+            pin.set_interrupt_enabled(interrupt, true);
+        }
+
+        Self { pin, edge }
+    }
+
+    /// Wait for at least one edge and return the edges counted since the
+    /// previous call: more than one when the task fell behind
+    pub async fn next(&mut self) -> u32 {
+        let id = self.pin.id();
+        let state = pin_state(id);
+
+        poll_fn(|cx| {
+            I::register_pin_waker(id, cx.waker());
+
+            match state.count.swap(0, Ordering::Relaxed) {
+                0 => Poll::Pending,
+                count => Poll::Ready(count),
+            }
+        })
+        .await
+    }
+
+    /// Edges counted and not yet reported, without waiting
+    pub fn pending(&self) -> u32 {
+        pin_state(self.pin.id()).count.load(Ordering::Relaxed)
+    }
+}
+
+impl<I, P> Drop for EdgeCounter<'_, I, P>
+where
+    I: hal::gpio::PinId,
+    P: hal::gpio::PullType,
+{
+    fn drop(&mut self) {
+        for &interrupt in self.edge.interrupts() {
+            self.pin.set_interrupt_enabled(interrupt, false);
+        }
+
+        pin_state(self.pin.id())
+            .armed
+            .store(false, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,176 @@
+use core::convert::Infallible;
+
+use embedded_hal::digital::{ErrorType, InputPin, OutputPin, StatefulOutputPin};
+use embedded_hal_async::digital::Wait;
+
+use super::{hal, wait_for_edge, wait_for_level, GpioInputPin, GpioOutputPin, PinWaker};
+
+/// Pin switched between driving and listening at runtime
+///
+/// The pin stays an SIO input, never driven by the SIO: the direction is
+/// switched by the output enable override and the level by the output
+/// override, so the pin keeps its waker slot and can be awaited in either
+/// direction.
+pub struct AsyncFlexPin<I, P>
+where
+    I: hal::gpio::PinId,
+    P: hal::gpio::PullType,
+{
+    pin: GpioInputPin<I, P>,
+    output: bool,
+}
+
+impl<I, P> AsyncFlexPin<I, P>
+where
+    I: hal::gpio::PinId + hal::gpio::ValidFunction<hal::gpio::FunctionSioInput>,
+    P: hal::gpio::PullType,
+{
+    /// Take over `pin` listening, with a low output level once driven
+    pub fn new<F: hal::gpio::Function>(mut pin: hal::gpio::Pin<I, F, P>) -> Self {
+        // the line is never driven: disabled before the conversion, which
+        // clears the SIO output enable, and again after it, as rp235x resets
+        // the overrides rewriting `io_ctrl`
+        pin.set_output_enable_override(hal::gpio::OutputEnableOverride::Disable);
+        let mut pin = pin.into_function::<hal::gpio::FunctionSioInput>();
+        pin.set_output_enable_override(hal::gpio::OutputEnableOverride::Disable);
+        pin.set_output_override(hal::gpio::OutputOverride::AlwaysLow);
+
+        Self { pin, output: false }
+    }
+}
+
+impl<I, P> AsyncFlexPin<I, P>
+where
+    I: hal::gpio::PinId + hal::gpio::ValidFunction<hal::gpio::FunctionSioOutput>,
+    P: hal::gpio::PullType,
+{
+    /// Give back the pin, driven at the output level last set
+    pub fn free(self) -> GpioOutputPin<I, P> {
+        let state = if self.level_high() {
+            hal::gpio::PinState::High
+        } else {
+            hal::gpio::PinState::Low
+        };
+
+        let mut pin = self.pin.into_push_pull_output_in_state(state);
+        pin.set_output_override(hal::gpio::OutputOverride::DontInvert);
+        pin.set_output_enable_override(hal::gpio::OutputEnableOverride::Normal);
+        pin
+    }
+}
+
+impl<I, P> AsyncFlexPin<I, P>
+where
+    I: hal::gpio::PinId,
+    P: hal::gpio::PullType,
+{
+    /// Stop driving the pin
+    pub fn set_as_input(&mut self) {
+        self.pin
+            .set_output_enable_override(hal::gpio::OutputEnableOverride::Disable);
+        self.output = false;
+    }
+
+    /// Drive the pin at the output level last set
+    pub fn set_as_output(&mut self) {
+        self.pin
+            .set_output_enable_override(hal::gpio::OutputEnableOverride::Enable);
+        self.output = true;
+    }
+
+    pub fn is_output(&self) -> bool {
+        self.output
+    }
+
+    fn level_high(&self) -> bool {
+        matches!(
+            self.pin.get_output_override(),
+            hal::gpio::OutputOverride::AlwaysHigh
+        )
+    }
+
+    /// Runtime id of the pin
+    pub fn id(&self) -> hal::gpio::DynPinId {
+        self.pin.id()
+    }
+}
+
+impl<I, P> ErrorType for AsyncFlexPin<I, P>
+where
+    I: hal::gpio::PinId,
+    P: hal::gpio::PullType,
+{
+    type Error = Infallible;
+}
+
+impl<I, P> InputPin for AsyncFlexPin<I, P>
+where
+    I: hal::gpio::PinId,
+    P: hal::gpio::PullType,
+{
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        self.pin.is_high()
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        self.pin.is_low()
+    }
+}
+
+impl<I, P> OutputPin for AsyncFlexPin<I, P>
+where
+    I: hal::gpio::PinId,
+    P: hal::gpio::PullType,
+{
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.pin
+            .set_output_override(hal::gpio::OutputOverride::AlwaysLow);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.pin
+            .set_output_override(hal::gpio::OutputOverride::AlwaysHigh);
+        Ok(())
+    }
+}
+
+impl<I, P> StatefulOutputPin for AsyncFlexPin<I, P>
+where
+    I: hal::gpio::PinId,
+    P: hal::gpio::PullType,
+{
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.level_high())
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.level_high())
+    }
+}
+
+impl<I, P> Wait for AsyncFlexPin<I, P>
+where
+    I: hal::gpio::PinId + PinWaker,
+    P: hal::gpio::PullType,
+{
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        wait_for_level::wait_for_high(&self.pin).await
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        wait_for_level::wait_for_low(&self.pin).await
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        wait_for_edge::wait_for_rising_edge(&self.pin).await
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        wait_for_edge::wait_for_falling_edge(&self.pin).await
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        wait_for_edge::wait_for_any_edge(&self.pin).await
+    }
+}
@@ -0,0 +1,132 @@
+use core::convert::Infallible;
+
+use embedded_hal::digital::{ErrorType, InputPin, OutputPin, StatefulOutputPin};
+use embedded_hal_async::digital::Wait;
+
+use crate::IntoAsync;
+
+use super::{hal, wait_for_edge, wait_for_level, GpioOutputPin, PinWaker};
+
+/// Output pin reading back the line level
+///
+/// The waits see the actual line, so an open-drain or wired-AND line can be
+/// awaited until the other devices release it.
+pub struct AsyncOutputPin<I, P>
+where
+    I: hal::gpio::PinId,
+    P: hal::gpio::PullType,
+{
+    pin: GpioOutputPin<I, P>,
+}
+
+impl<I, P> AsyncOutputPin<I, P>
+where
+    I: hal::gpio::PinId,
+    P: hal::gpio::PullType,
+{
+    fn new(mut pin: GpioOutputPin<I, P>) -> Self {
+        pin.set_input_enable(true);
+
+        Self { pin }
+    }
+
+    /// Runtime id of the pin
+    pub fn id(&self) -> hal::gpio::DynPinId {
+        self.pin.id()
+    }
+
+    pub fn free(self) -> GpioOutputPin<I, P> {
+        self.pin
+    }
+}
+
+impl<I, P> IntoAsync for GpioOutputPin<I, P>
+where
+    I: hal::gpio::PinId,
+    P: hal::gpio::PullType,
+{
+    type Target = AsyncOutputPin<I, P>;
+
+    fn into_async(self) -> Self::Target {
+        AsyncOutputPin::new(self)
+    }
+}
+
+impl<I, P> ErrorType for AsyncOutputPin<I, P>
+where
+    I: hal::gpio::PinId,
+    P: hal::gpio::PullType,
+{
+    type Error = Infallible;
+}
+
+impl<I, P> OutputPin for AsyncOutputPin<I, P>
+where
+    I: hal::gpio::PinId,
+    P: hal::gpio::PullType,
+{
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.pin.set_low().unwrap();
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.pin.set_high().unwrap();
+        Ok(())
+    }
+}
+
+impl<I, P> StatefulOutputPin for AsyncOutputPin<I, P>
+where
+    I: hal::gpio::PinId,
+    P: hal::gpio::PullType,
+{
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.pin.is_set_high().unwrap())
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.pin.is_set_low().unwrap())
+    }
+}
+
+/// Level of the line, not the level set
+impl<I, P> InputPin for AsyncOutputPin<I, P>
+where
+    I: hal::gpio::PinId,
+    P: hal::gpio::PullType,
+{
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        self.pin.as_input().is_high()
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        self.pin.as_input().is_low()
+    }
+}
+
+impl<I, P> Wait for AsyncOutputPin<I, P>
+where
+    I: hal::gpio::PinId + PinWaker,
+    P: hal::gpio::PullType,
+{
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        wait_for_level::wait_for_high(&self.pin).await
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        wait_for_level::wait_for_low(&self.pin).await
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        wait_for_edge::wait_for_rising_edge(&self.pin).await
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        wait_for_edge::wait_for_falling_edge(&self.pin).await
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        wait_for_edge::wait_for_any_edge(&self.pin).await
+    }
+}
@@ -0,0 +1,127 @@
+use core::sync::atomic::Ordering;
+
+use super::{hal, interrupt_bit, pin_state, PinState};
+
+/// Interrupts of a pin waited by one future
+///
+/// The interrupt handler disables every interrupt of the pin when one fires
+/// and counts it: any number of futures wait on the same pin, each one tells
+/// its own events from the count and enables its interrupts again if woken
+/// for someone else.
+pub(crate) struct PinWait {
+    interrupts: &'static [hal::gpio::Interrupt],
+    fired: [u32; 4],
+}
+
+impl PinWait {
+    pub(crate) const fn new(interrupts: &'static [hal::gpio::Interrupt]) -> Self {
+        Self {
+            interrupts,
+            fired: [0; 4],
+        }
+    }
+
+    /// First of the interrupts counted by the handler since `fired`
+    fn fired_since(&self, state: &PinState) -> Option<hal::gpio::Interrupt> {
+        self.interrupts.iter().copied().find(|&interrupt| {
+            let bit = interrupt_bit(interrupt);
+            state.fired[bit].load(Ordering::Relaxed) != self.fired[bit]
+        })
+    }
+
+    /// Count the events from now on and enable the interrupts
+    pub(crate) fn start<I, F, P>(&mut self, pin: &hal::gpio::Pin<I, F, P>)
+    where
+        I: hal::gpio::PinId,
+        F: hal::gpio::Function,
+        P: hal::gpio::PullType,
+    {
+        let id = pin.id();
+        let state = pin_state(id);
+
+        critical_section::with(|_| {
+            for &interrupt in self.interrupts {
+                // an edge latched with nobody waiting is not waited for
+                if state.waiting[interrupt_bit(interrupt)].fetch_add(1, Ordering::Relaxed) == 0 {
+                    clear_interrupt(id, interrupt);
+                }
+            }
+
+            self.fired = core::array::from_fn(|bit| state.fired[bit].load(Ordering::Relaxed));
+
+            for &interrupt in self.interrupts {
+                pin.set_interrupt_enabled(interrupt, true);
+            }
+        });
+    }
+
+    /// Whether one of the interrupts fired since [`PinWait::start`]
+    pub(crate) fn fired<I, F, P>(&self, pin: &hal::gpio::Pin<I, F, P>) -> bool
+    where
+        I: hal::gpio::PinId,
+        F: hal::gpio::Function,
+        P: hal::gpio::PullType,
+    {
+        self.fired_interrupt(pin).is_some()
+    }
+
+    /// The interrupt that fired since [`PinWait::start`], if any
+    pub(crate) fn fired_interrupt<I, F, P>(
+        &self,
+        pin: &hal::gpio::Pin<I, F, P>,
+    ) -> Option<hal::gpio::Interrupt>
+    where
+        I: hal::gpio::PinId,
+        F: hal::gpio::Function,
+        P: hal::gpio::PullType,
+    {
+        let state = pin_state(pin.id());
+
+        critical_section::with(|_| {
+            let interrupt = self.fired_since(state);
+            if interrupt.is_none() {
+                for &interrupt in self.interrupts {
+                    pin.set_interrupt_enabled(interrupt, true);
+                }
+            }
+
+            interrupt
+        })
+    }
+
+    /// Stop waiting, disabling the interrupts nobody else waits for
+    pub(crate) fn stop<I, F, P>(&self, pin: &hal::gpio::Pin<I, F, P>)
+    where
+        I: hal::gpio::PinId,
+        F: hal::gpio::Function,
+        P: hal::gpio::PullType,
+    {
+        let state = pin_state(pin.id());
+
+        critical_section::with(|_| {
+            for &interrupt in self.interrupts {
+                if state.waiting[interrupt_bit(interrupt)].fetch_sub(1, Ordering::Relaxed) == 1 {
+                    pin.set_interrupt_enabled(interrupt, false);
+                }
+            }
+        });
+    }
+}
+
+/// Clear a latched edge, write one to clear
+fn clear_interrupt(id: hal::gpio::DynPinId, interrupt: hal::gpio::Interrupt) {
+    let offset = usize::from(id.num) % 8 * 4 + interrupt_bit(interrupt);
+
+    match id.bank {
+        hal::gpio::DynBankId::Bank0 => {
+            let bank0 = unsafe { hal::pac::IO_BANK0::steal() };
+            bank0
+                .intr(usize::from(id.num) / 8)
+                .write(|w| unsafe { w.bits(1 << offset) });
+        }
+        hal::gpio::DynBankId::Qspi => {
+            let qspi = unsafe { hal::pac::IO_QSPI::steal() };
+            qspi.intr().write(|w| unsafe { w.bits(1 << offset) });
+        }
+    }
+}
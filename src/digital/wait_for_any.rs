@@ -0,0 +1,185 @@
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use embedded_hal::digital::InputPin;
+
+use super::pin_wait::PinWait;
+use super::{hal, AsyncInputPin, PinWaker};
+
+/// First event of [`wait_for_any`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PinEvent {
+    /// Position of the pin in the waited array
+    pub index: usize,
+    pub id: hal::gpio::DynPinId,
+    pub interrupt: hal::gpio::Interrupt,
+}
+
+/// Wait for the first of `interrupts` on any of `pins`
+///
+/// One future for the whole array: the pins share the task waker and every
+/// pin still armed gets its interrupts disabled when the future completes or
+/// is dropped. A level already present on the first poll completes at once.
+///
+/// The pins of an array share one type: typed pins are erased first with
+/// [`AsyncInputPin::into_dyn`].
+#[allow(private_bounds)]
+pub fn wait_for_any<'a, I, P, const N: usize>(
+    pins: &'a [AsyncInputPin<I, P>; N],
+    interrupts: &'static [hal::gpio::Interrupt],
+) -> impl Future<Output = PinEvent> + 'a
+where
+    I: hal::gpio::PinId + PinWaker,
+    P: hal::gpio::PullType,
+{
+    WaitForAny::new(pins, interrupts)
+}
+
+struct WaitForAny<'a, I, P, const N: usize>
+where
+    I: hal::gpio::PinId + PinWaker,
+    P: hal::gpio::PullType,
+{
+    pins: &'a [AsyncInputPin<I, P>; N],
+    interrupts: &'static [hal::gpio::Interrupt],
+    waits: [PinWait; N],
+    /// Pins with their interrupts enabled, one bit per index
+    waiting: u64,
+    polled: bool,
+    event: Option<PinEvent>,
+}
+
+impl<'a, I, P, const N: usize> WaitForAny<'a, I, P, N>
+where
+    I: hal::gpio::PinId + PinWaker,
+    P: hal::gpio::PullType,
+{
+    fn new(
+        pins: &'a [AsyncInputPin<I, P>; N],
+        interrupts: &'static [hal::gpio::Interrupt],
+    ) -> Self {
+        const { assert!(N <= u64::BITS as usize, "too many pins") };
+
+        Self {
+            pins,
+            interrupts,
+            waits: core::array::from_fn(|_| PinWait::new(interrupts)),
+            waiting: 0,
+            polled: false,
+            event: None,
+        }
+    }
+
+    fn event(&self, index: usize, interrupt: hal::gpio::Interrupt) -> PinEvent {
+        PinEvent {
+            index,
+            id: self.pins[index].id(),
+            interrupt,
+        }
+    }
+
+    /// Level among the interrupts already present on a pin
+    fn level(&self) -> Option<PinEvent> {
+        self.pins.iter().enumerate().find_map(|(index, pin)| {
+            let mut input = pin.pin.as_input();
+
+            self.interrupts
+                .iter()
+                .copied()
+                .find(|interrupt| match interrupt {
+                    hal::gpio::Interrupt::LevelHigh => input.is_high().unwrap_or(false),
+                    hal::gpio::Interrupt::LevelLow => input.is_low().unwrap_or(false),
+                    _ => false,
+                })
+                .map(|interrupt| self.event(index, interrupt))
+        })
+    }
+
+    fn fired(&self) -> Option<PinEvent> {
+        (0..N)
+            .filter(|index| self.waiting & (1 << index) != 0)
+            .find_map(|index| {
+                self.waits[index]
+                    .fired_interrupt(&self.pins[index].pin)
+                    .map(|interrupt| self.event(index, interrupt))
+            })
+    }
+
+    fn stop(&mut self) {
+        for index in 0..N {
+            if self.waiting & (1 << index) != 0 {
+                self.waits[index].stop(&self.pins[index].pin);
+            }
+        }
+
+        self.waiting = 0;
+    }
+}
+
+impl<'a, I, P, const N: usize> Unpin for WaitForAny<'a, I, P, N>
+where
+    I: hal::gpio::PinId + PinWaker,
+    P: hal::gpio::PullType,
+{
+}
+
+impl<'a, I, P, const N: usize> Future for WaitForAny<'a, I, P, N>
+where
+    I: hal::gpio::PinId + PinWaker,
+    P: hal::gpio::PullType,
+{
+    type Output = PinEvent;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if let Some(event) = this.event {
+            defmt::error!("poll invoked after ready");
+
+            return Poll::Ready(event);
+        }
+
+        if this.polled {
+            for pin in this.pins {
+                I::register_pin_waker(pin.id(), ctx.waker());
+            }
+
+            // woken for another future, of this task or of another one
+            let Some(event) = this.fired() else {
+                return Poll::Pending;
+            };
+
+            this.stop();
+            this.event = Some(event);
+
+            Poll::Ready(event)
+        } else {
+            this.polled = true;
+
+            if let Some(event) = this.level() {
+                this.event = Some(event);
+
+                return Poll::Ready(event);
+            }
+
+            for (index, pin) in this.pins.iter().enumerate() {
+                I::register_pin_waker(pin.id(), ctx.waker());
+
+                this.waits[index].start(&pin.pin);
+                this.waiting |= 1 << index;
+            }
+
+            Poll::Pending
+        }
+    }
+}
+
+impl<'a, I, P, const N: usize> Drop for WaitForAny<'a, I, P, N>
+where
+    I: hal::gpio::PinId + PinWaker,
+    P: hal::gpio::PullType,
+{
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -1,89 +1,131 @@
+use core::convert::Infallible;
 use core::future::Future;
-use core::marker::PhantomData;
 use core::pin::Pin;
 use core::task::{Context, Poll};
 
-use embedded_hal::digital::ErrorType;
+use super::pin_wait::PinWait;
+use super::{hal, PinWaker};
 
-use crate::WakerRegister;
-
-use super::{hal, GpioInputPin};
+pub(crate) fn wait_for_rising_edge<I, F, P>(
+    pin: &hal::gpio::Pin<I, F, P>,
+) -> WaitForEdge<'_, I, F, P>
+where
+    I: hal::gpio::PinId + PinWaker,
+    F: hal::gpio::Function,
+    P: hal::gpio::PullType,
+{
+    WaitForEdge::new(pin, &[hal::gpio::Interrupt::EdgeHigh])
+}
 
-/// Polymorphic wait for an edge
-pub(crate) trait WaitForEdgeInfo {
-    const INTERRUPT: hal::gpio::Interrupt;
+pub(crate) fn wait_for_falling_edge<I, F, P>(
+    pin: &hal::gpio::Pin<I, F, P>,
+) -> WaitForEdge<'_, I, F, P>
+where
+    I: hal::gpio::PinId + PinWaker,
+    F: hal::gpio::Function,
+    P: hal::gpio::PullType,
+{
+    WaitForEdge::new(pin, &[hal::gpio::Interrupt::EdgeLow])
 }
 
-pub(crate) struct EdgeHighInfo;
-impl WaitForEdgeInfo for EdgeHighInfo {
-    const INTERRUPT: hal::gpio::Interrupt = hal::gpio::Interrupt::EdgeHigh;
+pub(crate) fn wait_for_any_edge<I, F, P>(pin: &hal::gpio::Pin<I, F, P>) -> WaitForEdge<'_, I, F, P>
+where
+    I: hal::gpio::PinId + PinWaker,
+    F: hal::gpio::Function,
+    P: hal::gpio::PullType,
+{
+    WaitForEdge::new(
+        pin,
+        &[
+            hal::gpio::Interrupt::EdgeLow,
+            hal::gpio::Interrupt::EdgeHigh,
+        ],
+    )
 }
 
-pub(crate) struct EdgeLowInfo;
-impl WaitForEdgeInfo for EdgeLowInfo {
-    const INTERRUPT: hal::gpio::Interrupt = hal::gpio::Interrupt::EdgeLow;
+pub(crate) struct WaitForEdge<'a, I, F, P>
+where
+    I: hal::gpio::PinId + PinWaker,
+    F: hal::gpio::Function,
+    P: hal::gpio::PullType,
+{
+    pin: &'a hal::gpio::Pin<I, F, P>,
+    wait: PinWait,
+    polled: bool,
+    done: bool,
 }
 
-pub(crate) struct WaitForEdge<'a, INFO, I, P>
+impl<'a, I, F, P> WaitForEdge<'a, I, F, P>
 where
-    INFO: WaitForEdgeInfo,
-    I: hal::gpio::PinId + WakerRegister,
+    I: hal::gpio::PinId + PinWaker,
+    F: hal::gpio::Function,
     P: hal::gpio::PullType,
 {
-    pub(crate) pin: &'a mut GpioInputPin<I, P>,
-    pub(crate) polled: bool,
-    pub(crate) done: bool,
-    pub(crate) _info: PhantomData<INFO>,
+    fn new(pin: &'a hal::gpio::Pin<I, F, P>, interrupts: &'static [hal::gpio::Interrupt]) -> Self {
+        Self {
+            pin,
+            wait: PinWait::new(interrupts),
+            polled: false,
+            done: false,
+        }
+    }
 }
 
-impl<'a, INFO, I, P> Unpin for WaitForEdge<'a, INFO, I, P>
+impl<'a, I, F, P> Unpin for WaitForEdge<'a, I, F, P>
 where
-    INFO: WaitForEdgeInfo,
-    I: hal::gpio::PinId + WakerRegister,
+    I: hal::gpio::PinId + PinWaker,
+    F: hal::gpio::Function,
     P: hal::gpio::PullType,
 {
 }
 
-impl<'a, INFO, I, P> Future for WaitForEdge<'a, INFO, I, P>
+impl<'a, I, F, P> Future for WaitForEdge<'a, I, F, P>
 where
-    INFO: WaitForEdgeInfo,
-    I: hal::gpio::PinId + WakerRegister,
+    I: hal::gpio::PinId + PinWaker,
+    F: hal::gpio::Function,
     P: hal::gpio::PullType,
 {
-    type Output = Result<(), <GpioInputPin<I, P> as ErrorType>::Error>;
+    type Output = Result<(), Infallible>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
         let this = self.get_mut();
+        if this.done {
+            defmt::error!("poll invoked after ready");
+
+            return Poll::Ready(Ok(()));
+        }
+
+        I::register_pin_waker(this.pin.id(), ctx.waker());
+
         if this.polled {
-            if this.done {
-                defmt::error!("poll invoked after ready");
-            } else {
-                this.done = true;
-                this.pin.set_interrupt_enabled(INFO::INTERRUPT, false);
+            // woken for another future, of this task or of another one
+            if !this.wait.fired(this.pin) {
+                return Poll::Pending;
             }
 
+            this.done = true;
+            this.wait.stop(this.pin);
+
             Poll::Ready(Ok(()))
         } else {
             this.polled = true;
 
-            I::register_waker(ctx.waker());
-
-            this.pin.set_interrupt_enabled(INFO::INTERRUPT, true);
+            this.wait.start(this.pin);
 
             Poll::Pending
         }
     }
 }
 
-impl<'a, INFO, I, P> Drop for WaitForEdge<'a, INFO, I, P>
+impl<'a, I, F, P> Drop for WaitForEdge<'a, I, F, P>
 where
-    INFO: WaitForEdgeInfo,
-    I: hal::gpio::PinId + WakerRegister,
+    I: hal::gpio::PinId + PinWaker,
+    F: hal::gpio::Function,
     P: hal::gpio::PullType,
 {
     fn drop(&mut self) {
         if self.polled && !self.done {
-            self.pin.set_interrupt_enabled(INFO::INTERRUPT, false);
+            self.wait.stop(self.pin);
         }
     }
 }
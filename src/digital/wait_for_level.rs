@@ -1,92 +1,135 @@
+use core::convert::Infallible;
 use core::future::Future;
 use core::marker::PhantomData;
 use core::pin::Pin;
 use core::task::{Context, Poll};
 
-use embedded_hal::digital::{ErrorType, InputPin};
+use embedded_hal::digital::InputPin;
 
-use crate::WakerRegister;
-
-use super::{hal, GpioInputPin};
+use super::pin_wait::PinWait;
+use super::{hal, PinWaker};
 
 /// Polymorphic wait for a pin level
-pub(crate) trait WaitForLevelInfo<PIN>
-where
-    PIN: ErrorType,
-{
-    const INTERRUPT: hal::gpio::Interrupt;
+pub(crate) trait WaitForLevelInfo {
+    const INTERRUPTS: &'static [hal::gpio::Interrupt];
 
-    fn is_level(pin: &mut PIN) -> Result<bool, <PIN as ErrorType>::Error>;
+    fn is_level<PIN: InputPin>(pin: &mut PIN) -> Result<bool, PIN::Error>;
 }
 
 pub(crate) struct HighLevelInfo;
-impl<I: hal::gpio::PinId + WakerRegister, P: hal::gpio::PullType>
-    WaitForLevelInfo<GpioInputPin<I, P>> for HighLevelInfo
-{
-    const INTERRUPT: hal::gpio::Interrupt = hal::gpio::Interrupt::LevelHigh;
+impl WaitForLevelInfo for HighLevelInfo {
+    const INTERRUPTS: &'static [hal::gpio::Interrupt] = &[hal::gpio::Interrupt::LevelHigh];
 
-    fn is_level(
-        pin: &mut GpioInputPin<I, P>,
-    ) -> Result<bool, <GpioInputPin<I, P> as ErrorType>::Error> {
+    fn is_level<PIN: InputPin>(pin: &mut PIN) -> Result<bool, PIN::Error> {
         pin.is_high()
     }
 }
 
 pub(crate) struct LowLevelInfo;
-impl<I: hal::gpio::PinId + WakerRegister, P: hal::gpio::PullType>
-    WaitForLevelInfo<GpioInputPin<I, P>> for LowLevelInfo
-{
-    const INTERRUPT: hal::gpio::Interrupt = hal::gpio::Interrupt::LevelLow;
+impl WaitForLevelInfo for LowLevelInfo {
+    const INTERRUPTS: &'static [hal::gpio::Interrupt] = &[hal::gpio::Interrupt::LevelLow];
 
-    fn is_level(
-        pin: &mut GpioInputPin<I, P>,
-    ) -> Result<bool, <GpioInputPin<I, P> as ErrorType>::Error> {
+    fn is_level<PIN: InputPin>(pin: &mut PIN) -> Result<bool, PIN::Error> {
         pin.is_low()
     }
 }
 
-pub(crate) struct WaitForLevel<'a, INFO, I, P>
+pub(crate) fn wait_for_high<I, F, P>(
+    pin: &hal::gpio::Pin<I, F, P>,
+) -> WaitForLevel<'_, HighLevelInfo, I, F, P>
+where
+    I: hal::gpio::PinId + PinWaker,
+    F: hal::gpio::Function,
+    P: hal::gpio::PullType,
+{
+    WaitForLevel::new(pin)
+}
+
+pub(crate) fn wait_for_low<I, F, P>(
+    pin: &hal::gpio::Pin<I, F, P>,
+) -> WaitForLevel<'_, LowLevelInfo, I, F, P>
+where
+    I: hal::gpio::PinId + PinWaker,
+    F: hal::gpio::Function,
+    P: hal::gpio::PullType,
+{
+    WaitForLevel::new(pin)
+}
+
+pub(crate) struct WaitForLevel<'a, INFO, I, F, P>
 where
-    INFO: WaitForLevelInfo<GpioInputPin<I, P>>,
-    I: hal::gpio::PinId + WakerRegister,
+    INFO: WaitForLevelInfo,
+    I: hal::gpio::PinId + PinWaker,
+    F: hal::gpio::Function,
     P: hal::gpio::PullType,
 {
-    pub(crate) pin: &'a mut GpioInputPin<I, P>,
-    pub(crate) polled: bool,
-    pub(crate) done: bool,
-    pub(crate) _info: PhantomData<INFO>,
+    pin: &'a hal::gpio::Pin<I, F, P>,
+    wait: PinWait,
+    polled: bool,
+    done: bool,
+    _info: PhantomData<INFO>,
 }
 
-impl<'a, INFO, I, P> Unpin for WaitForLevel<'a, INFO, I, P>
+impl<'a, INFO, I, F, P> WaitForLevel<'a, INFO, I, F, P>
 where
-    INFO: WaitForLevelInfo<GpioInputPin<I, P>>,
-    I: hal::gpio::PinId + WakerRegister,
+    INFO: WaitForLevelInfo,
+    I: hal::gpio::PinId + PinWaker,
+    F: hal::gpio::Function,
+    P: hal::gpio::PullType,
+{
+    fn new(pin: &'a hal::gpio::Pin<I, F, P>) -> Self {
+        Self {
+            pin,
+            wait: PinWait::new(INFO::INTERRUPTS),
+            polled: false,
+            done: false,
+            _info: PhantomData,
+        }
+    }
+}
+
+impl<'a, INFO, I, F, P> Unpin for WaitForLevel<'a, INFO, I, F, P>
+where
+    INFO: WaitForLevelInfo,
+    I: hal::gpio::PinId + PinWaker,
+    F: hal::gpio::Function,
     P: hal::gpio::PullType,
 {
 }
 
-impl<'a, INFO, I, P> Future for WaitForLevel<'a, INFO, I, P>
+impl<'a, INFO, I, F, P> Future for WaitForLevel<'a, INFO, I, F, P>
 where
-    INFO: WaitForLevelInfo<GpioInputPin<I, P>>,
-    I: hal::gpio::PinId + WakerRegister,
+    INFO: WaitForLevelInfo,
+    I: hal::gpio::PinId + PinWaker,
+    F: hal::gpio::Function,
     P: hal::gpio::PullType,
 {
-    type Output = Result<(), <GpioInputPin<I, P> as ErrorType>::Error>;
+    type Output = Result<(), Infallible>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
         let this = self.get_mut();
+        if this.done {
+            defmt::error!("poll invoked after ready");
+
+            return Poll::Ready(Ok(()));
+        }
+
         if this.polled {
-            if this.done {
-                defmt::error!("poll invoked after ready");
-            } else {
-                this.done = true;
-                this.pin.set_interrupt_enabled(INFO::INTERRUPT, false);
+            I::register_pin_waker(this.pin.id(), ctx.waker());
+
+            // woken for another future, of this task or of another one
+            if !this.wait.fired(this.pin) {
+                return Poll::Pending;
             }
+
+            this.done = true;
+            this.wait.stop(this.pin);
+
             Poll::Ready(Ok(()))
         } else {
             this.polled = true;
 
-            let level = match INFO::is_level(this.pin) {
+            let level = match INFO::is_level(&mut this.pin.as_input()) {
                 Err(e) => {
                     this.done = true;
 
@@ -100,9 +143,9 @@ where
 
                 Poll::Ready(Ok(()))
             } else {
-                I::register_waker(ctx.waker());
+                I::register_pin_waker(this.pin.id(), ctx.waker());
 
-                this.pin.set_interrupt_enabled(INFO::INTERRUPT, true);
+                this.wait.start(this.pin);
 
                 Poll::Pending
             }
@@ -110,15 +153,16 @@ where
     }
 }
 
-impl<'a, INFO, I, P> Drop for WaitForLevel<'a, INFO, I, P>
+impl<'a, INFO, I, F, P> Drop for WaitForLevel<'a, INFO, I, F, P>
 where
-    INFO: WaitForLevelInfo<GpioInputPin<I, P>>,
-    I: hal::gpio::PinId + WakerRegister,
+    INFO: WaitForLevelInfo,
+    I: hal::gpio::PinId + PinWaker,
+    F: hal::gpio::Function,
     P: hal::gpio::PullType,
 {
     fn drop(&mut self) {
         if self.polled && !self.done {
-            self.pin.set_interrupt_enabled(INFO::INTERRUPT, false);
+            self.wait.stop(self.pin);
         }
     }
 }
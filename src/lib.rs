@@ -1,3 +1,18 @@
+//! Async drivers for the RP2040 and RP235x: an embassy time driver, alarm
+//! delays and GPIO waits
+//!
+//! # Resources
+//!
+//! The `time_driver` feature takes alarms 0 and 1 of its `Timer`, one per
+//! core: with it enabled only alarms 2 and 3 of that timer convert to an
+//! `AsyncAlarm`, earlier versions left alarm 1 available too.
+//!
+//! It also takes SIO spinlocks 29 and 30, one per core, to guard its alarms:
+//! the application must never claim them. `init` panics if one is held when
+//! it is called, but can not detect a later claim. With the
+//! `time_driver-critical-section` feature the global critical section is
+//! taken instead, as in the earlier versions.
+
 #![no_std]
 
 use core::task::Waker;
@@ -5,12 +20,39 @@ use core::task::Waker;
 #[cfg(feature = "delay")]
 mod delay;
 
+#[cfg(feature = "delay")]
+pub use delay::{AsyncAlarm, AsyncTicker, Overrun, SharedAlarm, SharedDelay};
+
 #[cfg(feature = "digital")]
 mod digital;
 
+#[cfg(feature = "digital")]
+pub use digital::{
+    wait_for_any, AsyncFlexPin, AsyncInputPin, AsyncOutputPin, Edge, EdgeCounter, PinEvent,
+    PinWatcher,
+};
+
 #[cfg(feature = "time_driver")]
 mod time_driver;
 
+#[cfg(feature = "time_driver")]
+pub use time_driver::{idle, TickConfig};
+
+#[cfg(feature = "time_driver-stats")]
+pub use time_driver::stats;
+
+#[cfg(any(feature = "delay", feature = "time_driver"))]
+mod timeout;
+
+#[cfg(any(feature = "delay", feature = "time_driver"))]
+pub use timeout::{Timeout, WithTimeout};
+
+#[cfg(target_arch = "riscv32")]
+mod xh3irq;
+
+#[cfg(target_arch = "riscv32")]
+pub use xh3irq::set_default_handler;
+
 #[cfg(feature = "rp235x")]
 use rp235x_hal as hal;
 
@@ -38,16 +80,52 @@ pub(crate) unsafe fn write_bitmask_clear(register: *mut u32, bits: u32) {
     core::ptr::write_volatile(register.byte_offset(0x3000), bits);
 }
 
-#[cfg(feature = "rp235x")]
-type Timer = hal::timer::Timer<hal::timer::CopyableTimer1>;
-
-#[cfg(feature = "rp2040")]
-type Timer = hal::timer::Timer;
+/// The timer backing the embassy time driver: `TIMER1`, or `TIMER0` with the
+/// `time_driver-timer0` feature
+#[cfg(all(
+    feature = "time_driver",
+    feature = "rp235x",
+    not(feature = "time_driver-timer0")
+))]
+pub type Timer = hal::timer::Timer<hal::timer::CopyableTimer1>;
+
+/// The timer backing the embassy time driver: `TIMER1`, or `TIMER0` with the
+/// `time_driver-timer0` feature
+#[cfg(all(
+    feature = "time_driver",
+    feature = "rp235x",
+    feature = "time_driver-timer0"
+))]
+pub type Timer = hal::timer::Timer<hal::timer::CopyableTimer0>;
+
+/// The timer backing the embassy time driver
+#[cfg(all(feature = "time_driver", feature = "rp2040"))]
+pub type Timer = hal::timer::Timer;
 
 /// # Safety
 #[cfg(feature = "time_driver")]
-pub unsafe fn init(timer: Timer) {
-    time_driver::init(timer);
+pub unsafe fn init(timer: Timer, tick: TickConfig) {
+    time_driver::init(timer, tick);
+
+    #[cfg(feature = "delay")]
+    delay::init();
+
+    #[cfg(feature = "digital")]
+    digital::init();
+}
+
+/// Unmask the interrupts of the time driver, of the delay alarms and of the
+/// pins on the calling core: the core that called [`init`] is set up
+/// already, the other one must call this before waiting for embassy-time
+/// timers, alarms or pins
+///
+/// # Safety
+pub unsafe fn init_core() {
+    #[cfg(feature = "time_driver")]
+    time_driver::init_core();
+
+    #[cfg(feature = "delay")]
+    delay::init_core();
 
     #[cfg(feature = "digital")]
     digital::init();
@@ -56,6 +134,15 @@ pub unsafe fn init(timer: Timer) {
 /// # Safety
 #[cfg(not(feature = "time_driver"))]
 pub unsafe fn init() {
+    init_without_time_driver();
+}
+
+/// Set up the delay alarms and the pins but not the time driver, with or
+/// without the `time_driver` feature: for the applications that do not use
+/// embassy-time, where [`init`] would take the timer with the feature on
+///
+/// # Safety
+pub unsafe fn init_without_time_driver() {
     #[cfg(feature = "delay")]
     delay::init();
 
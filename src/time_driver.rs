@@ -1,48 +1,135 @@
-use core::cell::Cell;
-use core::sync::atomic::Ordering;
+pub mod idle;
 
-use portable_atomic::AtomicU8;
+#[cfg(feature = "time_driver-stats")]
+pub mod stats;
+
+use core::cell::{Cell, RefCell};
+use core::task::Waker;
 
 #[cfg(feature = "rp235x")]
 use rp235x_hal as hal;
 
-#[cfg(feature = "rp235x")]
+#[cfg(all(feature = "rp235x", not(feature = "time_driver-timer0")))]
 use hal::pac::TIMER1 as TIMER;
 
+#[cfg(all(feature = "rp235x", not(feature = "time_driver-timer0")))]
+const TIMER_IRQS: [Interrupt; ALARM_COUNT] = [Interrupt::TIMER1_IRQ_0, Interrupt::TIMER1_IRQ_1];
+
+#[cfg(all(feature = "rp235x", feature = "time_driver-timer0"))]
+use hal::pac::TIMER0 as TIMER;
+
+#[cfg(all(feature = "rp235x", feature = "time_driver-timer0"))]
+const TIMER_IRQS: [Interrupt; ALARM_COUNT] = [Interrupt::TIMER0_IRQ_0, Interrupt::TIMER0_IRQ_1];
+
 #[cfg(feature = "rp2040")]
 use rp2040_hal as hal;
 
 #[cfg(feature = "rp2040")]
 use hal::pac::TIMER;
 
+#[cfg(feature = "rp2040")]
+const TIMER_IRQS: [Interrupt; ALARM_COUNT] = [Interrupt::TIMER_IRQ_0, Interrupt::TIMER_IRQ_1];
+
+#[cfg(all(feature = "rp2040", feature = "time_driver-timer0"))]
+compile_error!("time_driver-timer0 is available on rp235x only");
+
+use hal::pac::Interrupt;
 use hal::timer::Alarm;
 
-use critical_section::Mutex;
+use embassy_time_driver::{Driver, TICK_HZ};
+use embassy_time_queue_utils::Queue;
 
-use embassy_time_driver::{AlarmHandle, Driver};
+use crate::{get_current_core, Timer, NUM_CORES};
 
-use hal::pac::interrupt;
+/// One alarm per core: alarm `n` wakes the timers scheduled on core `n` and
+/// its interrupt is unmasked on that core only
+const ALARM_COUNT: usize = NUM_CORES;
 
-use crate::Timer;
+/// Alarm `n` is guarded by the hardware spinlock `SPINLOCK_BASE + n`, the
+/// `critical-section` implementation of the HAL takes spinlock 31
+///
+/// The HAL can not reserve them: the application must never claim these
+/// spinlocks. [`init`] only panics if one is held at that instant, a later
+/// claim goes unnoticed and breaks the alarms
+#[cfg(not(feature = "time_driver-critical-section"))]
+const SPINLOCK_BASE: usize = 29;
 
-#[allow(clippy::type_complexity)]
 struct AlarmState {
+    /// Full 64 bit deadline, `u64::MAX` when there is nothing to wait for.
+    ///
+    /// The hardware compares only the low 32 bits of the counter, so a
+    /// deadline further than `u32::MAX` ticks away matches once per
+    /// wraparound before the real one: the interrupt handler re-arms the
+    /// alarm until the high word is reached too, without waking anyone.
+    /// That is one interrupt every 2^32 ticks, about 71 minutes at 1 MHz,
+    /// the least a 32 bit comparator allows: arming a wraparound alarm until
+    /// the high word matches would take as many, see [`rearms`].
     timestamp: Cell<u64>,
-    callback: Cell<Option<(fn(*mut ()), *mut ())>>,
+
+    queue: RefCell<Queue>,
+
+    #[cfg(feature = "time_driver-stats")]
+    stats: Cell<stats::AlarmStats>,
 }
-unsafe impl Send for AlarmState {}
 
-const ALARM_COUNT: usize = 4;
+impl AlarmState {
+    const fn new() -> Self {
+        Self {
+            timestamp: Cell::new(u64::MAX),
+            queue: RefCell::new(Queue::new()),
+            #[cfg(feature = "time_driver-stats")]
+            stats: Cell::new(stats::AlarmStats::new()),
+        }
+    }
+}
+
+/// Counter value of the first match after `now` of an alarm armed with the
+/// low word of `timestamp`
+const fn next_match(now: u64, timestamp: u64) -> u64 {
+    let ticks = (timestamp as u32).wrapping_sub(now as u32);
+    if ticks == 0 {
+        now + (1 << 32)
+    } else {
+        now + ticks as u64
+    }
+}
+
+/// Interrupts re-arming the alarm before `timestamp` is reached from `now`,
+/// as handled by `TimerDriver::check_alarm`
+const fn rearms(now: u64, timestamp: u64) -> u64 {
+    let mut now = now;
+    let mut rearms = 0;
+    loop {
+        now = next_match(now, timestamp);
+        if timestamp <= now {
+            assert!(now == timestamp, "the last match is the deadline");
+
+            return rearms;
+        }
+        rearms += 1;
+    }
+}
+
+// a one day timer at 1 MHz is served by one interrupt per wraparound, then
+// fires once at the deadline
+const _: () = assert!(rearms(0, 86_400_000_000) == 20);
+const _: () = assert!(rearms(u32::MAX as u64, u32::MAX as u64 + (3 << 32)) == 2);
+const _: () = assert!(rearms(1_000, 1_000 + u32::MAX as u64) == 0);
+
+struct Alarms([AlarmState; ALARM_COUNT]);
+
+// only reached through `TimerDriver::with_alarm`, which holds the spinlock of
+// the alarm with the interrupts of the calling core masked, or the critical
+// section
+unsafe impl Sync for Alarms {}
 
 struct TimerDriver {
-    alarms: Mutex<[AlarmState; ALARM_COUNT]>,
-    next_alarm: AtomicU8,
+    alarms: Alarms,
 }
 
 embassy_time_driver::time_driver_impl!(
     static DRIVER: TimerDriver = TimerDriver {
-        alarms: Mutex::new([const { AlarmState { timestamp: Cell::new(u64::MAX), callback: Cell::new(None) } }; ALARM_COUNT]),
-        next_alarm: AtomicU8::new(0),
+        alarms: Alarms([const { AlarmState::new() }; ALARM_COUNT]),
     }
 );
 
@@ -60,119 +147,312 @@ impl Driver for TimerDriver {
         }
     }
 
-    unsafe fn allocate_alarm(&self) -> Option<AlarmHandle> {
-        let id = self
-            .next_alarm
-            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |x| {
-                if x < ALARM_COUNT as u8 {
-                    Some(x + 1)
-                } else {
-                    None
+    fn schedule_wake(&self, at: u64, waker: &Waker) {
+        // timers are polled by the executor of the current core, which is
+        // the one that must be woken up
+        let n = get_current_core();
+
+        self.with_alarm(n, |alarm| {
+            let mut queue = alarm.queue.borrow_mut();
+            if queue.schedule_wake(at, waker) {
+                let mut next = queue.next_expiration(self.now());
+                while !self.set_alarm(alarm, n, next) {
+                    next = queue.next_expiration(self.now());
                 }
-            });
+            }
+        });
+    }
+}
+
+impl TimerDriver {
+    /// Exclusive access to alarm `n`: the other core is held back only while
+    /// it touches the same alarm, not for every alarm operation as with a
+    /// critical section
+    #[cfg(not(feature = "time_driver-critical-section"))]
+    fn with_alarm<R>(&self, n: usize, f: impl FnOnce(&AlarmState) -> R) -> R {
+        interrupt_free(|| {
+            let sio = unsafe { &*hal::pac::SIO::ptr() };
+            let lock = sio.spinlock(SPINLOCK_BASE + n);
+
+            // reading a spinlock claims it, zero when already claimed
+            while lock.read().bits() == 0 {}
 
-        match id {
-            Ok(id) => Some(AlarmHandle::new(id)),
-            Err(_) => {
-                defmt::warn!("cannot allocate alarm");
-                None
+            let result = f(&self.alarms.0[n]);
+
+            unsafe { lock.write_with_zero(|w| w.bits(1)) };
+
+            result
+        })
+    }
+
+    /// Exclusive access to alarm `n` through the global critical section,
+    /// the locking the per-alarm spinlocks replaced
+    #[cfg(feature = "time_driver-critical-section")]
+    fn with_alarm<R>(&self, n: usize, f: impl FnOnce(&AlarmState) -> R) -> R {
+        critical_section::with(|_| f(&self.alarms.0[n]))
+    }
+
+    fn set_alarm(&self, alarm: &AlarmState, n: usize, timestamp: u64) -> bool {
+        alarm.timestamp.set(timestamp);
+
+        Self::arm(n, timestamp);
+
+        let now = self.now();
+        if timestamp <= now {
+            // the alarm would never fire: disarm it and let the caller
+            // process the expired timers
+            Self::disarm(n);
+
+            alarm.timestamp.set(u64::MAX);
+
+            false
+        } else {
+            #[cfg(feature = "time_driver-stats")]
+            if timestamp != u64::MAX {
+                stats::update(&alarm.stats, |stats| stats.set = stats.set.wrapping_add(1));
             }
+
+            true
         }
     }
 
-    fn set_alarm_callback(&self, alarm: AlarmHandle, callback: fn(*mut ()), context: *mut ()) {
-        let n = alarm.id() as usize;
-        critical_section::with(|cs| {
-            let alarm = &self.alarms.borrow(cs)[n];
-            alarm.callback.set(Some((callback, context)));
-        });
+    fn arm(n: usize, timestamp: u64) {
+        if timestamp == u64::MAX {
+            // nothing scheduled, do not wake up every wraparound
+            Self::disarm(n);
+        } else {
+            let timer = unsafe { TIMER::steal() };
+            let alarm = match n {
+                0 => timer.alarm0().as_ptr(),
+                _ => timer.alarm1().as_ptr(),
+            };
+            unsafe { alarm.write_volatile(timestamp as u32) };
+        }
+    }
+
+    fn disarm(n: usize) {
+        let timer = unsafe { TIMER::steal() };
+        unsafe {
+            timer.armed().write_with_zero(|w| w.bits(1 << n));
+        }
     }
 
-    fn set_alarm(&self, alarm: AlarmHandle, timestamp: u64) -> bool {
-        let n = alarm.id() as usize;
-        critical_section::with(|cs| {
-            let alarm = &self.alarms.borrow(cs)[n];
-            alarm.timestamp.set(timestamp);
+    fn check_alarm(&self, n: usize) {
+        #[cfg(feature = "time_driver-stats")]
+        let entry = stats::timestamp();
 
+        self.with_alarm(n, |alarm| {
             let timer = unsafe { TIMER::steal() };
-            match n {
-                0 => timer
-                    .alarm0()
-                    .write(|w| unsafe { w.bits(timestamp as u32) }),
-                1 => timer
-                    .alarm1()
-                    .write(|w| unsafe { w.bits(timestamp as u32) }),
-                2 => timer
-                    .alarm2()
-                    .write(|w| unsafe { w.bits(timestamp as u32) }),
-                3 => timer
-                    .alarm3()
-                    .write(|w| unsafe { w.bits(timestamp as u32) }),
-                _ => unreachable!(),
+
+            #[cfg(feature = "time_driver-stats")]
+            if !stats::pending(n) {
+                stats::update(&alarm.stats, |stats| {
+                    stats.spurious = stats.spurious.wrapping_add(1)
+                });
             }
 
-            let now = self.now();
-            if timestamp <= now {
-                unsafe {
-                    timer.armed().write_with_zero(|w| w.bits(1 << n));
-                }
+            unsafe {
+                timer.intr().write_with_zero(|w| w.bits(1 << n));
+            }
 
-                alarm.timestamp.set(u64::MAX);
+            let timestamp = alarm.timestamp.get();
+            if timestamp <= self.now() {
+                #[cfg(feature = "time_driver-stats")]
+                stats::record_fire(&alarm.stats, entry, timestamp);
 
-                false
+                self.trigger_alarm(alarm, n);
             } else {
-                true
+                // matched on the low 32 bits only: the deadline is at least
+                // one wraparound away, arm it again without waking anyone
+                Self::arm(n, timestamp);
             }
-        })
+        });
+    }
+
+    fn trigger_alarm(&self, alarm: &AlarmState, n: usize) {
+        let mut queue = alarm.queue.borrow_mut();
+        let mut next = queue.next_expiration(self.now());
+        while !self.set_alarm(alarm, n, next) {
+            next = queue.next_expiration(self.now());
+        }
+    }
+
+    /// Earliest deadline among all the cores
+    fn next_deadline(&self) -> u64 {
+        (0..ALARM_COUNT)
+            .map(|n| self.with_alarm(n, |alarm| alarm.timestamp.get()))
+            .min()
+            .unwrap_or(u64::MAX)
+    }
+
+    /// Any of the alarms not used by the time driver armed, their deadlines
+    /// would be skipped by [`TimerDriver::resume`]
+    fn other_alarms_armed(&self) -> bool {
+        let timer = unsafe { TIMER::steal() };
+
+        timer.armed().read().bits() & !((1 << ALARM_COUNT) - 1) != 0
+    }
+
+    /// Stop the counter, returning the time it stopped at
+    fn pause(&self) -> u64 {
+        let timer = unsafe { TIMER::steal() };
+        timer.pause().write(|w| w.pause().set_bit());
+
+        self.now()
+    }
+
+    /// Restart the counter from `now`: the deadlines that elapsed meanwhile
+    /// were skipped by the alarm comparators and are processed here
+    fn resume(&self, now: u64) {
+        let timer = unsafe { TIMER::steal() };
+        timer.timelw().write(|w| unsafe { w.bits(now as u32) });
+        timer
+            .timehw()
+            .write(|w| unsafe { w.bits((now >> 32) as u32) });
+        timer.pause().write(|w| w.pause().clear_bit());
+
+        for n in 0..ALARM_COUNT {
+            self.with_alarm(n, |alarm| self.trigger_alarm(alarm, n));
+        }
     }
 }
 
-impl TimerDriver {
-    fn check_alarm(&self, n: usize) {
-        critical_section::with(|cs| {
-            let timer = unsafe { TIMER::steal() };
+#[cfg(target_arch = "arm")]
+fn interrupt_free<R>(f: impl FnOnce() -> R) -> R {
+    cortex_m::interrupt::free(|_| f())
+}
 
-            let alarm = &self.alarms.borrow(cs)[n];
-            let timestamp = alarm.timestamp.get();
-            if timestamp <= self.now() {
-                unsafe {
-                    timer.armed().write_with_zero(|w| w.bits(1 << n));
-                }
+#[cfg(target_arch = "riscv32")]
+fn interrupt_free<R>(f: impl FnOnce() -> R) -> R {
+    hal::arch::interrupt_free(f)
+}
+
+/// Tick of the timer backing the time driver, it must match the embassy-time
+/// `tick-hz-*` feature
+#[derive(Clone, Copy, defmt::Format)]
+pub enum TickConfig {
+    /// Keep the 1 MHz tick set up by the HAL clocks initialization
+    Default,
+    /// Tick every `cycles` cycles of `clk_ref`, running at `reference_hz`
+    ///
+    /// On rp2040 the tick generator is shared with the watchdog.
+    Reference { reference_hz: u32, cycles: u16 },
+    /// Count every cycle of `clk_sys`, running at `sys_hz`, bypassing the
+    /// tick generator: the time follows any later change of `clk_sys`
+    #[cfg(feature = "rp235x")]
+    SystemClock { sys_hz: u32 },
+}
 
-                alarm.timestamp.set(u64::MAX);
+impl TickConfig {
+    const DEFAULT_TICK_HZ: u64 = 1_000_000;
+
+    /// Tick from `clk_ref` checked against the embassy-time tick rate: in a
+    /// const context a mismatch fails the build
+    pub const fn reference(reference_hz: u32, cycles: u16) -> Self {
+        assert!(
+            cycles > 0 && cycles < 512,
+            "the tick generator counts 1 to 511 cycles"
+        );
+        assert!(
+            reference_hz % cycles as u32 == 0,
+            "clk_ref must be a multiple of the tick"
+        );
+        assert!(
+            (reference_hz / cycles as u32) as u64 == TICK_HZ,
+            "the tick must match the embassy-time tick-hz feature"
+        );
+
+        Self::Reference {
+            reference_hz,
+            cycles,
+        }
+    }
 
-                if let Some((f, ctx)) = alarm.callback.get() {
-                    f(ctx);
-                }
+    /// Count `clk_sys` cycles checked against the embassy-time tick rate: in
+    /// a const context a mismatch fails the build
+    #[cfg(feature = "rp235x")]
+    pub const fn system_clock(sys_hz: u32) -> Self {
+        assert!(
+            sys_hz as u64 == TICK_HZ,
+            "clk_sys must match the embassy-time tick-hz feature"
+        );
+
+        Self::SystemClock { sys_hz }
+    }
+
+    pub const fn tick_hz(&self) -> u64 {
+        match self {
+            Self::Default => Self::DEFAULT_TICK_HZ,
+            Self::Reference {
+                reference_hz,
+                cycles,
+            } => (*reference_hz / *cycles as u32) as u64,
+            #[cfg(feature = "rp235x")]
+            Self::SystemClock { sys_hz } => *sys_hz as u64,
+        }
+    }
+
+    fn apply(&self) {
+        #[cfg(feature = "rp235x")]
+        {
+            let timer = unsafe { TIMER::steal() };
+            timer.source().write(|w| match self {
+                Self::SystemClock { .. } => w.clk_sys().clk_sys(),
+                _ => w.clk_sys().tick(),
+            });
+        }
+
+        if let Self::Reference { cycles, .. } = *self {
+            #[cfg(feature = "rp2040")]
+            {
+                let watchdog = unsafe { hal::pac::WATCHDOG::steal() };
+                watchdog
+                    .tick()
+                    .write(|w| unsafe { w.cycles().bits(cycles).enable().set_bit() });
             }
 
-            unsafe {
-                timer.intr().write_with_zero(|w| w.bits(1 << n));
+            #[cfg(feature = "rp235x")]
+            {
+                let ticks = unsafe { hal::pac::TICKS::steal() };
+
+                #[cfg(not(feature = "time_driver-timer0"))]
+                let tick = ticks.ticktimer1();
+
+                #[cfg(feature = "time_driver-timer0")]
+                let tick = ticks.ticktimer0();
+
+                tick.ctrl().write(|w| w.enable().clear_bit());
+                tick.cycles()
+                    .write(|w| unsafe { w.proc0_cycles().bits(cycles) });
+                tick.ctrl().write(|w| w.enable().set_bit());
             }
-        });
+        }
     }
 }
 
-pub(crate) unsafe fn init(mut timer: Timer) {
-    #[cfg(all(target_arch = "arm", feature = "rp235x"))]
-    {
-        cortex_m::peripheral::NVIC::unmask(interrupt::TIMER1_IRQ_0);
-        cortex_m::peripheral::NVIC::unmask(interrupt::TIMER1_IRQ_1);
-        cortex_m::peripheral::NVIC::unmask(interrupt::TIMER1_IRQ_2);
-        cortex_m::peripheral::NVIC::unmask(interrupt::TIMER1_IRQ_3);
+pub(crate) unsafe fn init(mut timer: Timer, tick: TickConfig) {
+    if tick.tick_hz() != TICK_HZ {
+        defmt::panic!(
+            "timer tick {} Hz does not match the embassy-time tick {} Hz",
+            tick.tick_hz(),
+            TICK_HZ
+        );
     }
 
-    #[cfg(all(target_arch = "arm", feature = "rp2040"))]
-    {
-        cortex_m::peripheral::NVIC::unmask(interrupt::TIMER_IRQ_0);
-        cortex_m::peripheral::NVIC::unmask(interrupt::TIMER_IRQ_1);
-        cortex_m::peripheral::NVIC::unmask(interrupt::TIMER_IRQ_2);
-        cortex_m::peripheral::NVIC::unmask(interrupt::TIMER_IRQ_3);
-    }
+    tick.apply();
 
-    #[cfg(target_arch = "riscv32")]
+    // best effort: a spinlock claimed later by the application is not seen
+    #[cfg(not(feature = "time_driver-critical-section"))]
     {
-        todo!();
+        let sio = unsafe { &*hal::pac::SIO::ptr() };
+        let mask = ((1 << ALARM_COUNT) - 1) << SPINLOCK_BASE;
+        if sio.spinlock_st().read().bits() & mask != 0 {
+            defmt::panic!(
+                "spinlocks {} to {} are reserved to the time driver",
+                SPINLOCK_BASE,
+                SPINLOCK_BASE + ALARM_COUNT - 1
+            );
+        }
     }
 
     let mut alarm_0 = timer.alarm_0().unwrap();
@@ -183,53 +463,68 @@ pub(crate) unsafe fn init(mut timer: Timer) {
     alarm_1.enable_interrupt();
     core::mem::forget(alarm_1);
 
-    let mut alarm_2 = timer.alarm_2().unwrap();
-    alarm_2.enable_interrupt();
-    core::mem::forget(alarm_2);
+    init_core();
+}
+
+/// Unmask the alarm interrupt of the calling core
+pub(crate) unsafe fn init_core() {
+    let irq = TIMER_IRQS[get_current_core()];
+
+    #[cfg(target_arch = "arm")]
+    {
+        cortex_m::peripheral::NVIC::unmask(irq);
+    }
 
-    let mut alarm_3 = timer.alarm_3().unwrap();
-    alarm_3.enable_interrupt();
-    core::mem::forget(alarm_3);
+    #[cfg(target_arch = "riscv32")]
+    {
+        crate::xh3irq::unmask(irq);
+        crate::xh3irq::enable();
+    }
 }
 
-#[cfg(feature = "rp235x")]
-mod inner {
+#[cfg(all(feature = "rp235x", not(feature = "time_driver-timer0")))]
+pub(crate) mod inner {
     use super::{hal::pac::interrupt, DRIVER};
 
+    #[cfg(target_arch = "riscv32")]
+    pub(crate) const EXTERNAL_INTERRUPTS: &[(interrupt::Interrupt, fn())] = &[
+        (interrupt::Interrupt::TIMER1_IRQ_0, TIMER1_IRQ_0),
+        (interrupt::Interrupt::TIMER1_IRQ_1, TIMER1_IRQ_1),
+    ];
+
     #[cfg_attr(target_arch = "arm", interrupt)]
-    #[cfg_attr(
-        target_arch = "riscv32",
-        riscv_rt::external_interrupt(interrupt::TIMER1_IRQ_0)
-    )]
+    #[cfg_attr(target_arch = "riscv32", allow(non_snake_case))]
     fn TIMER1_IRQ_0() {
         DRIVER.check_alarm(0);
     }
 
     #[cfg_attr(target_arch = "arm", interrupt)]
-    #[cfg_attr(
-        target_arch = "riscv32",
-        riscv_rt::external_interrupt(interrupt::TIMER1_IRQ_1)
-    )]
+    #[cfg_attr(target_arch = "riscv32", allow(non_snake_case))]
     fn TIMER1_IRQ_1() {
         DRIVER.check_alarm(1);
     }
+}
+
+#[cfg(all(feature = "rp235x", feature = "time_driver-timer0"))]
+pub(crate) mod inner {
+    use super::{hal::pac::interrupt, DRIVER};
+
+    #[cfg(target_arch = "riscv32")]
+    pub(crate) const EXTERNAL_INTERRUPTS: &[(interrupt::Interrupt, fn())] = &[
+        (interrupt::Interrupt::TIMER0_IRQ_0, TIMER0_IRQ_0),
+        (interrupt::Interrupt::TIMER0_IRQ_1, TIMER0_IRQ_1),
+    ];
 
     #[cfg_attr(target_arch = "arm", interrupt)]
-    #[cfg_attr(
-        target_arch = "riscv32",
-        riscv_rt::external_interrupt(interrupt::TIMER1_IRQ_2)
-    )]
-    fn TIMER1_IRQ_2() {
-        DRIVER.check_alarm(2);
+    #[cfg_attr(target_arch = "riscv32", allow(non_snake_case))]
+    fn TIMER0_IRQ_0() {
+        DRIVER.check_alarm(0);
     }
 
     #[cfg_attr(target_arch = "arm", interrupt)]
-    #[cfg_attr(
-        target_arch = "riscv32",
-        riscv_rt::external_interrupt(interrupt::TIMER1_IRQ_3)
-    )]
-    fn TIMER1_IRQ_3() {
-        DRIVER.check_alarm(3);
+    #[cfg_attr(target_arch = "riscv32", allow(non_snake_case))]
+    fn TIMER0_IRQ_1() {
+        DRIVER.check_alarm(1);
     }
 }
 
@@ -238,38 +533,12 @@ mod inner {
     use super::{hal::pac::interrupt, DRIVER};
 
     #[cfg_attr(target_arch = "arm", interrupt)]
-    #[cfg_attr(
-        target_arch = "riscv32",
-        riscv_rt::external_interrupt(interrupt::TIMER_IRQ_0)
-    )]
     fn TIMER_IRQ_0() {
         DRIVER.check_alarm(0);
     }
 
     #[cfg_attr(target_arch = "arm", interrupt)]
-    #[cfg_attr(
-        target_arch = "riscv32",
-        riscv_rt::external_interrupt(interrupt::TIMER_IRQ_1)
-    )]
     fn TIMER_IRQ_1() {
         DRIVER.check_alarm(1);
     }
-
-    #[cfg_attr(target_arch = "arm", interrupt)]
-    #[cfg_attr(
-        target_arch = "riscv32",
-        riscv_rt::external_interrupt(interrupt::TIMER_IRQ_2)
-    )]
-    fn TIMER_IRQ_2() {
-        DRIVER.check_alarm(2);
-    }
-
-    #[cfg_attr(target_arch = "arm", interrupt)]
-    #[cfg_attr(
-        target_arch = "riscv32",
-        riscv_rt::external_interrupt(interrupt::TIMER_IRQ_3)
-    )]
-    fn TIMER_IRQ_3() {
-        DRIVER.check_alarm(3);
-    }
 }
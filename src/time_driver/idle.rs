@@ -0,0 +1,344 @@
+//! Tickless idle for the embassy time driver
+//!
+//! These are meant to be called from the idle loop of the executor, in place
+//! of `wfe`: [`idle`] puts the core in deep sleep gating the clocks that are
+//! not needed until the next interrupt, [`idle_with`] also stops the timer when
+//! the next deadline is far enough, keeping the time with a low power
+//! [`Timebase`] and resynchronising the time driver on wakeup.
+
+use embassy_time_driver::Driver;
+
+use super::{hal, interrupt_free, DRIVER};
+
+/// Clocks left running while the core is in deep sleep, as the raw
+/// `CLOCKS.SLEEP_EN0` and `CLOCKS.SLEEP_EN1` values
+#[derive(Clone, Copy, defmt::Format)]
+pub struct SleepClocks {
+    pub en0: u32,
+    pub en1: u32,
+}
+
+impl SleepClocks {
+    /// Keep every clock running, as after reset
+    pub const ALL: Self = Self {
+        en0: u32::MAX,
+        en1: u32::MAX,
+    };
+
+    pub const fn new(en0: u32, en1: u32) -> Self {
+        Self { en0, en1 }
+    }
+}
+
+/// Low power counter keeping the time while the timer is stopped
+pub trait Timebase {
+    /// Shortest sleep, in microseconds, worth stopping the timer for
+    ///
+    /// Each sleep on the timebase moves the time driver forward by the lower
+    /// bound of the elapsed time, losing less than two [`resolution`]s: the
+    /// threshold should be many resolutions long.
+    ///
+    /// [`resolution`]: Timebase::resolution
+    fn threshold(&self) -> u64;
+
+    /// Step of [`now`](Timebase::now), in microseconds
+    fn resolution(&self) -> u64;
+
+    /// Current time, in microseconds
+    fn now(&mut self) -> u64;
+
+    /// Wake up the core at or before `at`, in microseconds of this timebase
+    fn set_wakeup(&mut self, at: u64);
+
+    /// Cancel the wakeup and clear its pending interrupt
+    fn clear_wakeup(&mut self);
+}
+
+/// Next deadline of the time driver, `None` if no task is waiting for a timer
+pub fn next_deadline() -> Option<u64> {
+    let deadline = DRIVER.next_deadline();
+    if deadline == u64::MAX {
+        None
+    } else {
+        Some(deadline)
+    }
+}
+
+/// Sleep until the next interrupt or event, gating the clocks not in `clocks`
+pub fn idle(clocks: SleepClocks) {
+    with_sleep_clocks(clocks, || deep_sleep(false));
+}
+
+/// Like [`idle`], but when the next deadline is further than the timebase
+/// threshold the timer is paused and `timebase` wakes the core up: on wakeup
+/// the time driver is moved forward by the time elapsed on `timebase`
+///
+/// The time driver never gets ahead of the real time, but falls behind by
+/// up to two timebase resolutions on each wakeup, also when woken by another
+/// interrupt: embassy timers fire late, never early.
+///
+/// The timer is shared by both cores: with executors on both of them, call
+/// this only when the other core is idle as well. It is also shared with the
+/// alarms 2 and 3 of the timer, as `AsyncAlarm` or through the HAL: while
+/// one of them is armed the timer keeps running and this is the same as
+/// [`idle`].
+pub fn idle_with<T: Timebase>(timebase: &mut T, clocks: SleepClocks) {
+    interrupt_free(|| {
+        let now = DRIVER.now();
+        let deadline = DRIVER.next_deadline();
+        if deadline.saturating_sub(now) < timebase.threshold() || DRIVER.other_alarms_armed() {
+            with_sleep_clocks(clocks, || deep_sleep(true));
+            return;
+        }
+
+        let start = timebase.now();
+        if deadline != u64::MAX {
+            timebase.set_wakeup(start + (deadline - now));
+        }
+
+        let paused = DRIVER.pause();
+
+        with_sleep_clocks(clocks, || deep_sleep(true));
+
+        // both readings are up to one resolution behind the real time: the
+        // lower bound of the elapsed time, added to the paused counter
+        let elapsed = timebase
+            .now()
+            .saturating_sub(start)
+            .saturating_sub(timebase.resolution());
+        timebase.clear_wakeup();
+
+        DRIVER.resume(paused + elapsed);
+    });
+}
+
+fn with_sleep_clocks(clocks: SleepClocks, f: impl FnOnce()) {
+    let pac_clocks = unsafe { hal::pac::CLOCKS::steal() };
+
+    let en0 = pac_clocks.sleep_en0().read().bits();
+    let en1 = pac_clocks.sleep_en1().read().bits();
+
+    pac_clocks
+        .sleep_en0()
+        .write(|w| unsafe { w.bits(clocks.en0) });
+    pac_clocks
+        .sleep_en1()
+        .write(|w| unsafe { w.bits(clocks.en1) });
+
+    f();
+
+    pac_clocks.sleep_en0().write(|w| unsafe { w.bits(en0) });
+    pac_clocks.sleep_en1().write(|w| unsafe { w.bits(en1) });
+}
+
+#[cfg(target_arch = "arm")]
+fn deep_sleep(masked: bool) {
+    const SCR_SEVONPEND: u32 = 1 << 4;
+
+    let mut scb = unsafe { cortex_m::Peripherals::steal() }.SCB;
+    scb.set_sleepdeep();
+    if masked {
+        // interrupts masked by the caller must still wake up the core
+        unsafe { scb.scr.modify(|scr| scr | SCR_SEVONPEND) };
+    }
+
+    cortex_m::asm::dsb();
+    cortex_m::asm::wfe();
+
+    if masked {
+        unsafe { scb.scr.modify(|scr| scr & !SCR_SEVONPEND) };
+    }
+    scb.clear_sleepdeep();
+}
+
+#[cfg(target_arch = "riscv32")]
+fn deep_sleep(masked: bool) {
+    // MSLEEP.DEEPSLEEP
+    unsafe { core::arch::asm!("csrsi 0xbf0, 1") };
+
+    if masked {
+        riscv::asm::wfi();
+    } else {
+        hal::arch::wfe();
+    }
+
+    unsafe { core::arch::asm!("csrci 0xbf0, 1") };
+}
+
+#[cfg(feature = "rp235x")]
+pub use powman::PowmanTimebase;
+
+#[cfg(feature = "rp235x")]
+mod powman {
+    use super::{hal, Timebase};
+
+    /// [`Timebase`] on the POWMAN always-on timer, with a resolution of one
+    /// millisecond
+    ///
+    /// The always-on timer must be running.
+    pub struct PowmanTimebase {
+        powman: hal::powman::Powman,
+        threshold: u64,
+    }
+
+    impl PowmanTimebase {
+        pub fn new(powman: hal::powman::Powman, threshold: u64) -> Self {
+            #[cfg(target_arch = "riscv32")]
+            unsafe {
+                crate::xh3irq::unmask(hal::pac::Interrupt::POWMAN_IRQ_TIMER);
+            }
+
+            Self { powman, threshold }
+        }
+
+        pub fn free(self) -> hal::powman::Powman {
+            self.powman
+        }
+    }
+
+    impl Timebase for PowmanTimebase {
+        fn threshold(&self) -> u64 {
+            self.threshold
+        }
+
+        fn resolution(&self) -> u64 {
+            1_000
+        }
+
+        fn now(&mut self) -> u64 {
+            self.powman.aot_get_time() * 1_000
+        }
+
+        fn set_wakeup(&mut self, at: u64) {
+            self.powman.aot_set_alarm(at / 1_000);
+            self.powman.aot_alarm_enable();
+            self.powman.aot_alarm_interrupt_enable();
+        }
+
+        fn clear_wakeup(&mut self) {
+            self.powman.aot_alarm_clear();
+            self.powman.aot_alarm_interrupt_disable();
+
+            #[cfg(target_arch = "arm")]
+            cortex_m::peripheral::NVIC::unpend(hal::pac::Interrupt::POWMAN_IRQ_TIMER);
+        }
+    }
+}
+
+#[cfg(feature = "rp2040")]
+pub use rtc::RtcTimebase;
+
+#[cfg(feature = "rp2040")]
+mod rtc {
+    use super::{hal, Timebase};
+
+    use hal::rtc::{DateTime, DateTimeFilter, DayOfWeek, RealTimeClock};
+
+    const MICROS_PER_SECOND: u64 = 1_000_000;
+    const SECONDS_PER_DAY: u64 = 86_400;
+
+    /// [`Timebase`] on the RTC, with a resolution of one second
+    pub struct RtcTimebase {
+        rtc: RealTimeClock,
+        threshold: u64,
+    }
+
+    impl RtcTimebase {
+        pub fn new(rtc: RealTimeClock, threshold: u64) -> Self {
+            Self { rtc, threshold }
+        }
+
+        pub fn free(self) -> RealTimeClock {
+            self.rtc
+        }
+    }
+
+    impl Timebase for RtcTimebase {
+        fn threshold(&self) -> u64 {
+            self.threshold
+        }
+
+        fn resolution(&self) -> u64 {
+            MICROS_PER_SECOND
+        }
+
+        fn now(&mut self) -> u64 {
+            to_seconds(&self.rtc.now().unwrap()) * MICROS_PER_SECOND
+        }
+
+        fn set_wakeup(&mut self, at: u64) {
+            let at = from_seconds(at / MICROS_PER_SECOND);
+
+            self.rtc.schedule_alarm(
+                DateTimeFilter::default()
+                    .year(at.year)
+                    .month(at.month)
+                    .day(at.day)
+                    .hour(at.hour)
+                    .minute(at.minute)
+                    .second(at.second),
+            );
+            self.rtc.enable_interrupt();
+        }
+
+        fn clear_wakeup(&mut self) {
+            self.rtc.disable_alarm();
+            self.rtc.clear_interrupt();
+            self.rtc.disable_interrupt();
+
+            cortex_m::peripheral::NVIC::unpend(hal::pac::Interrupt::RTC_IRQ);
+        }
+    }
+
+    /// Years are shifted by one 400 years era to keep them positive
+    const YEARS_OFFSET: u64 = 400;
+
+    /// Seconds since -0400-03-01
+    fn to_seconds(t: &DateTime) -> u64 {
+        let year = u64::from(t.year) + YEARS_OFFSET;
+        let (year, month) = if t.month > 2 {
+            (year, u64::from(t.month) - 3)
+        } else {
+            (year - 1, u64::from(t.month) + 9)
+        };
+        let days = year * 365 + year / 4 - year / 100
+            + year / 400
+            + (153 * month + 2) / 5
+            + u64::from(t.day)
+            - 1;
+
+        days * SECONDS_PER_DAY
+            + u64::from(t.hour) * 3_600
+            + u64::from(t.minute) * 60
+            + u64::from(t.second)
+    }
+
+    fn from_seconds(seconds: u64) -> DateTime {
+        let days = seconds / SECONDS_PER_DAY;
+        let seconds = seconds % SECONDS_PER_DAY;
+
+        let era = days / 146_097;
+        let day_of_era = days % 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let (year, month) = if month < 10 {
+            (era * 400 + year_of_era, month + 3)
+        } else {
+            (era * 400 + year_of_era + 1, month - 9)
+        };
+
+        DateTime {
+            year: (year - YEARS_OFFSET) as u16,
+            month: month as u8,
+            day: day as u8,
+            // not part of the alarm filter
+            day_of_week: DayOfWeek::Sunday,
+            hour: (seconds / 3_600) as u8,
+            minute: (seconds / 60 % 60) as u8,
+            second: (seconds % 60) as u8,
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Alarm diagnostics of the time driver
+//!
+//! The counters tell apart deadlines missed by the driver, an interrupt
+//! served late or an alarm armed too late, from tasks that are slow to run
+//! once woken.
+
+use core::cell::Cell;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use embassy_time_driver::TICK_HZ;
+
+use super::{ALARM_COUNT, DRIVER, TIMER};
+
+/// Number of alarm slots used by the time driver, one per core
+pub const ALARM_SLOTS: usize = ALARM_COUNT;
+
+/// Counters of one alarm slot
+#[derive(Clone, Copy, Default, defmt::Format)]
+pub struct AlarmStats {
+    /// Deadlines armed in the hardware
+    pub set: u32,
+    /// Fires with a latency above the late threshold
+    pub late: u32,
+    /// Worst latency, in ticks, between the deadline and the interrupt
+    /// handler reading `timerawl`
+    pub worst_latency: u32,
+    /// Interrupts without a pending alarm match
+    pub spurious: u32,
+}
+
+impl AlarmStats {
+    pub(super) const fn new() -> Self {
+        Self {
+            set: 0,
+            late: 0,
+            worst_latency: 0,
+            spurious: 0,
+        }
+    }
+}
+
+/// 100 µs
+static LATE_THRESHOLD: AtomicU32 = AtomicU32::new(late_threshold_default());
+
+const fn late_threshold_default() -> u32 {
+    let ticks = TICK_HZ / 10_000;
+    if ticks == 0 {
+        1
+    } else {
+        ticks as u32
+    }
+}
+
+/// Counters of every alarm slot
+pub fn alarm_stats() -> [AlarmStats; ALARM_SLOTS] {
+    core::array::from_fn(|n| DRIVER.with_alarm(n, |alarm| alarm.stats.get()))
+}
+
+/// Clear the counters of every alarm slot
+pub fn reset() {
+    for n in 0..ALARM_SLOTS {
+        DRIVER.with_alarm(n, |alarm| alarm.stats.set(AlarmStats::new()));
+    }
+}
+
+/// Latency, in ticks, above which a fire is counted as late
+pub fn set_late_threshold(ticks: u32) {
+    LATE_THRESHOLD.store(ticks, Ordering::Relaxed);
+}
+
+pub(super) fn update(stats: &Cell<AlarmStats>, f: impl FnOnce(&mut AlarmStats)) {
+    let mut value = stats.get();
+    f(&mut value);
+    stats.set(value);
+}
+
+/// Raw counter low word, read first thing in the interrupt handler
+pub(super) fn timestamp() -> u32 {
+    let timer = unsafe { TIMER::steal() };
+    timer.timerawl().read().bits()
+}
+
+/// Whether the interrupt of alarm `n` is actually raised
+pub(super) fn pending(n: usize) -> bool {
+    let timer = unsafe { TIMER::steal() };
+    timer.intr().read().bits() & (1 << n) != 0
+}
+
+pub(super) fn record_fire(stats: &Cell<AlarmStats>, entry: u32, deadline: u64) {
+    // the comparator matches at the deadline, a negative distance can only
+    // come from a deadline reached by software
+    let latency = entry.wrapping_sub(deadline as u32);
+    let latency = if (latency as i32) < 0 { 0 } else { latency };
+    let threshold = LATE_THRESHOLD.load(Ordering::Relaxed);
+
+    update(stats, |stats| {
+        stats.worst_latency = stats.worst_latency.max(latency);
+        if latency > threshold {
+            stats.late = stats.late.wrapping_add(1);
+        }
+    });
+}
@@ -0,0 +1,97 @@
+//! Timeouts for any future
+//!
+//! The future and the timer are polled together: the one that loses the race
+//! is dropped when the race ends. A timer on an `AsyncAlarm` then disarms
+//! the alarm and clears its interrupt, ready for the next wait.
+
+use core::future::{poll_fn, Future};
+use core::pin::pin;
+use core::task::Poll;
+
+#[cfg(feature = "delay")]
+use crate::delay::{AlarmCounter, AsyncAlarm};
+
+#[cfg(feature = "delay")]
+use crate::WakerRegister;
+
+#[cfg(all(feature = "delay", feature = "rp235x"))]
+use rp235x_hal as hal;
+
+#[cfg(all(feature = "delay", feature = "rp2040"))]
+use rp2040_hal as hal;
+
+/// The timer elapsed before the future completed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub struct Timeout;
+
+#[allow(private_bounds)]
+pub trait WithTimeout: Future + Sized {
+    /// Give up after `timeout` on the time driver
+    #[cfg(feature = "time_driver")]
+    fn with_timeout(
+        self,
+        timeout: embassy_time::Duration,
+    ) -> impl Future<Output = Result<Self::Output, Timeout>> {
+        race(self, embassy_time::Timer::after(timeout))
+    }
+
+    /// Give up at `deadline` on the time driver
+    #[cfg(feature = "time_driver")]
+    fn with_deadline(
+        self,
+        deadline: embassy_time::Instant,
+    ) -> impl Future<Output = Result<Self::Output, Timeout>> {
+        race(self, embassy_time::Timer::at(deadline))
+    }
+
+    /// Give up after `timeout` on `alarm`
+    #[cfg(feature = "delay")]
+    fn with_alarm_timeout<'a, A>(
+        self,
+        alarm: &'a mut AsyncAlarm<A>,
+        timeout: fugit::MicrosDurationU64,
+    ) -> impl Future<Output = Result<Self::Output, Timeout>> + 'a
+    where
+        Self: 'a,
+        A: hal::timer::Alarm + AlarmCounter + WakerRegister,
+    {
+        let deadline = A::now() + timeout;
+        race(self, alarm.wait_until(deadline))
+    }
+
+    /// Give up at `deadline` on `alarm`
+    #[cfg(feature = "delay")]
+    fn with_alarm_deadline<'a, A>(
+        self,
+        alarm: &'a mut AsyncAlarm<A>,
+        deadline: hal::timer::Instant,
+    ) -> impl Future<Output = Result<Self::Output, Timeout>> + 'a
+    where
+        Self: 'a,
+        A: hal::timer::Alarm + AlarmCounter + WakerRegister,
+    {
+        race(self, alarm.wait_until(deadline))
+    }
+}
+
+impl<F: Future> WithTimeout for F {}
+
+async fn race<F, T>(future: F, timer: T) -> Result<F::Output, Timeout>
+where
+    F: Future,
+    T: Future,
+{
+    let mut future = pin!(future);
+    let mut timer = pin!(timer);
+
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            Poll::Ready(Ok(output))
+        } else if timer.as_mut().poll(cx).is_ready() {
+            Poll::Ready(Err(Timeout))
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
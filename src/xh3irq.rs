@@ -0,0 +1,130 @@
+//! Hazard3 external interrupt controller (Xh3irq)
+//!
+//! `riscv-rt` only knows about the `MachineExternal` trap: the handler below
+//! asks the controller for the pending IRQs and dispatches them to the
+//! handlers of this crate. The other IRQs go to the handler set with
+//! [`set_default_handler`], or are logged and masked without one.
+
+use core::arch::asm;
+use core::cell::Cell;
+
+use critical_section::Mutex;
+
+use crate::hal::pac::Interrupt;
+
+const NUM_IRQS: usize = 46;
+
+type Handler = fn();
+type DefaultHandler = fn(u16);
+
+static DEFAULT_HANDLER: Mutex<Cell<Option<DefaultHandler>>> = Mutex::new(Cell::new(None));
+
+static HANDLERS: [Option<Handler>; NUM_IRQS] = {
+    #[allow(unused_mut)]
+    let mut handlers: [Option<Handler>; NUM_IRQS] = [None; NUM_IRQS];
+
+    #[cfg(feature = "time_driver")]
+    register(
+        &mut handlers,
+        crate::time_driver::inner::EXTERNAL_INTERRUPTS,
+    );
+
+    #[cfg(feature = "delay")]
+    register(&mut handlers, crate::delay::inner::EXTERNAL_INTERRUPTS);
+
+    handlers
+};
+
+#[allow(dead_code)]
+const fn register(handlers: &mut [Option<Handler>; NUM_IRQS], irqs: &[(Interrupt, Handler)]) {
+    let mut i = 0;
+    while i < irqs.len() {
+        handlers[irqs[i].0 as usize] = Some(irqs[i].1);
+        i += 1;
+    }
+}
+
+/// Handle the external interrupts with no handler in this crate, called
+/// with the IRQ number: it must clear the source of the interrupt
+pub fn set_default_handler(handler: DefaultHandler) {
+    critical_section::with(|cs| DEFAULT_HANDLER.borrow(cs).set(Some(handler)));
+}
+
+/// Enable the external interrupts and the global machine interrupt flag
+///
+/// # Safety
+pub(crate) unsafe fn enable() {
+    riscv::register::mie::set_mext();
+    riscv::interrupt::enable();
+}
+
+/// # Safety
+pub(crate) unsafe fn unmask(irq: Interrupt) {
+    unmask_n(irq as u16);
+}
+
+unsafe fn unmask_n(irq: u16) {
+    // MEIEA: bits [4:0] select the 16 IRQs window, bits [31:16] the IRQs in it
+    let bits = (1u32 << (16 + irq % 16)) | u32::from(irq / 16);
+    asm!("csrs 0xbe0, {0}", in(reg) bits);
+}
+
+unsafe fn mask_n(irq: u16) {
+    let bits = (1u32 << (16 + irq % 16)) | u32::from(irq / 16);
+    asm!("csrc 0xbe0, {0}", in(reg) bits);
+}
+
+fn read_meicontext() -> u32 {
+    let meicontext: u32;
+    unsafe {
+        asm!("csrr {0}, 0xbe5", out(reg) meicontext);
+    }
+    meicontext
+}
+
+unsafe fn write_meicontext(meicontext: u32) {
+    asm!("csrw 0xbe5, {0}", in(reg) meicontext);
+}
+
+fn next_interrupt() -> Option<u16> {
+    let meinext: u32;
+
+    // MEINEXT: read the highest priority pending IRQ and update MEICONTEXT
+    unsafe {
+        asm!("csrrsi {0}, 0xbe4, 1", out(reg) meinext);
+    }
+
+    if meinext & (1 << 31) != 0 {
+        None
+    } else {
+        Some(((meinext >> 2) & 0x1ff) as u16)
+    }
+}
+
+fn unhandled(irq: u16) {
+    match critical_section::with(|cs| DEFAULT_HANDLER.borrow(cs).get()) {
+        Some(handler) => handler(irq),
+        None => {
+            defmt::error!("unhandled external interrupt {}, masked", irq);
+
+            // the source would stay pending forever
+            unsafe { mask_n(irq) };
+        }
+    }
+}
+
+#[no_mangle]
+extern "C" fn MachineExternal() {
+    // each MEINEXT update raises the preemption priority in MEICONTEXT: it
+    // is restored on the way out, as the SDK handler does
+    let meicontext = read_meicontext();
+
+    while let Some(irq) = next_interrupt() {
+        match HANDLERS.get(usize::from(irq)).copied().flatten() {
+            Some(handler) => handler(),
+            None => unhandled(irq),
+        }
+    }
+
+    unsafe { write_meicontext(meicontext) };
+}